@@ -1,28 +1,422 @@
-use anyhow::Context;
+use crate::date_helper::{parse_weekday, weekday_name};
+use anyhow::{anyhow, Context};
+use chrono::{Datelike, Weekday};
+use serde::Deserialize;
+use serde_json::{Map, Value};
 use std::collections::btree_map::BTreeMap;
+use std::ops::Range;
+use std::path::PathBuf;
 
 type Day = chrono::NaiveDate;
 
-#[derive(Serialize, Deserialize, Debug)]
-struct AbsenceConfig {
+/// Kinds of absence per day with the share of the day, see [get_days_of_absence]
+pub type Absences = BTreeMap<Day, BTreeMap<AbsenceKind, f32>>;
+
+/// Category of an absence, untyped entries count as vacation
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum AbsenceKind {
+    #[default]
+    #[serde(alias = "urlaub")]
+    Vacation,
+    #[serde(alias = "krank")]
+    Sick,
+    #[serde(alias = "kind_krank")]
+    ChildSick,
+    #[serde(alias = "dienstreise")]
+    BusinessTrip,
+    #[serde(alias = "gleitzeit")]
+    OvertimeCompensation,
+    #[serde(alias = "sonderurlaub")]
+    SpecialLeave,
+    /// Regular day off of part-timers
+    #[serde(alias = "frei")]
+    DayOff,
+}
+
+impl std::str::FromStr for AbsenceKind {
+    type Err = anyhow::Error;
+
+    /// Same names as in `absence.json`, e.g. `sick` or `krank`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use serde::de::IntoDeserializer;
+
+        AbsenceKind::deserialize(s.into_deserializer())
+            .map_err(|e: serde::de::value::Error| anyhow!("Unknown kind of absence: {e}"))
+    }
+}
+
+impl AbsenceKind {
+    pub const ALL: [AbsenceKind; 7] = [
+        AbsenceKind::Vacation,
+        AbsenceKind::Sick,
+        AbsenceKind::ChildSick,
+        AbsenceKind::BusinessTrip,
+        AbsenceKind::OvertimeCompensation,
+        AbsenceKind::SpecialLeave,
+        AbsenceKind::DayOff,
+    ];
+
+    /// Column title in the report
+    pub fn name(self) -> &'static str {
+        match self {
+            AbsenceKind::Vacation => "Urlaub",
+            AbsenceKind::Sick => "Krank",
+            AbsenceKind::ChildSick => "Kind krank",
+            AbsenceKind::BusinessTrip => "Dienstreise",
+            AbsenceKind::OvertimeCompensation => "Gleitzeit",
+            AbsenceKind::SpecialLeave => "Sonderurlaub",
+            AbsenceKind::DayOff => "Frei",
+        }
+    }
+
+    /// Overtime compensation is paid with the overtime, so the target hours stay the same.
+    /// All other absences are credited.
+    pub fn reduces_target(self) -> bool {
+        self != AbsenceKind::OvertimeCompensation
+    }
+}
+
+/// Entries further away from today are most likely typos. Old entries are kept
+/// for the vacation balance, so the past gets more room.
+const MAX_YEARS_AHEAD: i64 = 2;
+const MAX_YEARS_BACK: i64 = 10;
+
+/// Time entries on this project and/or activity are absences of the given kind,
+/// e.g. `{"project": 42, "activity": 9, "kind": "sick"}`
+#[derive(Deserialize, Debug, Clone)]
+pub struct AbsenceBooking {
+    pub project: Option<u64>,
+    pub activity: Option<u64>,
+    #[serde(default)]
+    pub kind: AbsenceKind,
+}
+
+impl AbsenceBooking {
+    pub fn matches(&self, project: u64, activity: u64) -> bool {
+        self.project.unwrap_or(project) == project && self.activity.unwrap_or(activity) == activity
+    }
+}
+
+/// Contents of `absence.json`
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct AbsenceConfig {
     #[serde(flatten)]
-    inner: BTreeMap<String, Absence>,
+    inner: BTreeMap<String, Entry>,
 }
 
 impl AbsenceConfig {
-    fn to_days(&self) -> Vec<Day> {
-        self.inner.values().fold(Vec::new(), |mut accum, v| {
-            accum.extend(v.clone().into_iter());
-            accum
+    pub fn load() -> Result<AbsenceConfig, anyhow::Error> {
+        if let Some(config_file) = config_file() {
+            use std::fs::File;
+
+            let file = File::open(&config_file).with_context(|| {
+                format!(
+                    "Could not parse days of absence configuration at {config_file:#?}.
+     Please create file; you can check check git repo
+     https://github.com/oleid/redmine-helper for examples."
+                )
+            })?;
+            serde_json::from_reader(file).with_context(|| {
+                format!("Could not parse days of absence configuration at {config_file:#?}",)
+            })
+        } else {
+            Ok(AbsenceConfig::default())
+        }
+    }
+
+    /// Days of absence between `from` and `to`, with the share of the day which is absent
+    /// per kind. Absences given in hours are converted using the working hours of that day.
+    pub fn days_of_absence(
+        &self,
+        from: Day,
+        to: Day,
+        hours_per_day: &dyn Fn(&Day) -> f32,
+    ) -> Absences {
+        self.to_days(hours_per_day)
+            .into_iter()
+            .filter(|(day, _)| day >= &from && day <= &to)
+            .collect()
+    }
+
+    /// First and last day of all entries
+    pub fn span(&self) -> Option<(Day, Day)> {
+        let days = self.inner.values().flat_map(|entry| {
+            let (_, absence) = entry.parts();
+            [absence.first_day(), absence.last_day()]
+        });
+        Some((days.clone().min()?, days.max()?))
+    }
+
+    /// Problems of entries touching `from..=to`: reversed ranges, invalid shares of a day,
+    /// overlaps, entries which are only on weekends or holidays and dates too far from
+    /// `today`.
+    pub fn check(
+        &self,
+        from: Day,
+        to: Day,
+        today: Day,
+        hours_per_day: &dyn Fn(&Day) -> f32,
+        is_day_off: &dyn Fn(&Day) -> bool,
+    ) -> Vec<String> {
+        let mut warnings = Vec::new();
+        let mut shares: BTreeMap<Day, Vec<(&str, f32)>> = BTreeMap::new();
+        let years = |years| chrono::Duration::days(365 * years);
+
+        for (name, entry) in &self.inner {
+            let (_, absence) = entry.parts();
+            let (first_day, last_day) = (absence.first_day(), absence.last_day());
+            if first_day.max(last_day) < from || first_day.min(last_day) > to {
+                continue;
+            }
+
+            if last_day < first_day {
+                warnings.push(format!(
+                    "'{name}': last day {last_day} is before first day {first_day}, no day is counted"
+                ));
+                continue;
+            }
+
+            let share = absence.share(hours_per_day);
+            if share <= 0.0 || share > 1.0 {
+                warnings.push(format!(
+                    "'{name}': {} is not a valid part of a day",
+                    absence.extent()
+                ));
+            }
+            if absence.clone().into_iter().all(|day| is_day_off(&day)) {
+                warnings.push(format!(
+                    "'{name}' is only on weekends or holidays, no day is counted"
+                ));
+            }
+            if first_day > today + years(MAX_YEARS_AHEAD) {
+                warnings.push(format!(
+                    "'{name}' is more than {MAX_YEARS_AHEAD} years in the future, is {first_day} correct?"
+                ));
+            }
+            if last_day < today - years(MAX_YEARS_BACK) {
+                warnings.push(format!(
+                    "'{name}' is more than {MAX_YEARS_BACK} years in the past, is {last_day} correct?"
+                ));
+            }
+
+            // other absences are expected to span the recurring ones
+            if !absence.is_recurring() {
+                for day in absence.clone() {
+                    shares.entry(day).or_default().push((name, share));
+                }
+            }
+        }
+
+        let mut overlaps: BTreeMap<(&str, &str), Vec<Day>> = BTreeMap::new();
+        for (day, entries) in &shares {
+            if entries.iter().map(|(_, share)| share).sum::<f32>() > 1.0 {
+                for (i, (first, _)) in entries.iter().enumerate() {
+                    for (second, _) in &entries[i + 1..] {
+                        overlaps.entry((first, second)).or_default().push(*day);
+                    }
+                }
+            }
+        }
+        for ((first, second), days) in overlaps {
+            warnings.push(format!(
+                "'{first}' and '{second}' overlap on {} day(s), starting {}",
+                days.len(),
+                days[0]
+            ));
+        }
+
+        warnings
+    }
+
+    /// Share of each day which is absent, by kind. Several entries for the same day add up,
+    /// but never to more than the whole day. Recurring days off come first, so that e.g.
+    /// a vacation spanning them doesn't use up vacation days.
+    fn to_days(&self, hours_per_day: &dyn Fn(&Day) -> f32) -> Absences {
+        let mut entries = self.inner.values().collect::<Vec<_>>();
+        entries.sort_by_key(|entry| !entry.parts().1.is_recurring());
+
+        entries
+            .into_iter()
+            .fold(BTreeMap::new(), |mut accum, entry| {
+                let (kind, absence) = entry.parts();
+                let share = absence.share(hours_per_day);
+                for day in absence.clone() {
+                    let kinds = accum.entry(day).or_insert_with(BTreeMap::new);
+                    let left = 1.0 - kinds.values().sum::<f32>();
+                    *kinds.entry(kind).or_insert(0.0) += f32::min(share, left);
+                }
+                accum
+            })
+    }
+}
+
+/// An absence, optionally with its kind, e.g. `{"kind": "sick", "day": "2018-10-15"}`.
+/// Recurring absences without kind are days off, all others vacation.
+#[derive(Serialize, Debug, Clone)]
+#[serde(untagged)]
+enum Entry {
+    Typed {
+        kind: AbsenceKind,
+        #[serde(flatten)]
+        absence: Absence,
+    },
+    Plain(Absence),
+}
+
+/// Unknown kinds and fields are rejected, instead of falling back to a whole day of vacation
+impl<'de> Deserialize<'de> for Entry {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        let mut value = Value::deserialize(deserializer)?;
+        let kind = value
+            .as_object_mut()
+            .and_then(|map| map.remove("kind"))
+            .map(|kind| AbsenceKind::deserialize(kind).map_err(D::Error::custom))
+            .transpose()?;
+        let absence = Absence::deserialize(&value)
+            .map_err(|_| D::Error::custom(format!("Not a valid absence: {value}")))?;
+        if let Absence::Recurring { interval: 0, .. } = absence {
+            return Err(D::Error::custom(format!(
+                "interval needs to be at least 1 week: {value}"
+            )));
+        }
+
+        Ok(match kind {
+            Some(kind) => Entry::Typed { kind, absence },
+            None => Entry::Plain(absence),
         })
     }
 }
 
+impl Entry {
+    fn parts(&self) -> (AbsenceKind, &Absence) {
+        match self {
+            Entry::Typed { kind, absence } => (*kind, absence),
+            Entry::Plain(absence) if absence.is_recurring() => (AbsenceKind::DayOff, absence),
+            Entry::Plain(absence) => (AbsenceKind::default(), absence),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
-#[serde(untagged)] // hides the variant type in serialization format
+#[serde(untagged, deny_unknown_fields)] // hides the variant type in serialization format
 enum Absence {
     SingleDay(Day),
-    MultiDay { first_day: Day, last_day: Day },
+    /// Every `interval`th week on `weekday` from `first_day` up to `last_day`,
+    /// except the given days
+    Recurring {
+        #[serde(deserialize_with = "deserialize_weekday")]
+        weekday: Weekday,
+        #[serde(default = "one_week")]
+        interval: u32,
+        first_day: Day,
+        last_day: Day,
+        #[serde(default)]
+        except: Vec<Day>,
+    },
+    MultiDay {
+        first_day: Day,
+        last_day: Day,
+    },
+    Hours {
+        day: Day,
+        hours: f32,
+    },
+    Fraction {
+        day: Day,
+        fraction: f32,
+    },
+    WholeDay {
+        day: Day,
+    },
+}
+
+fn one_week() -> u32 {
+    1
+}
+
+fn deserialize_weekday<'de, D>(deserializer: D) -> Result<Weekday, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let name = String::deserialize(deserializer)?;
+    parse_weekday(&name)
+        .ok_or_else(|| serde::de::Error::custom(format!("Unknown weekday '{name}'")))
+}
+
+impl Absence {
+    fn first_day(&self) -> Day {
+        match self {
+            Absence::SingleDay(day)
+            | Absence::Hours { day, .. }
+            | Absence::Fraction { day, .. }
+            | Absence::WholeDay { day } => *day,
+            Absence::MultiDay { first_day, .. } | Absence::Recurring { first_day, .. } => {
+                *first_day
+            }
+        }
+    }
+
+    fn last_day(&self) -> Day {
+        match self {
+            Absence::MultiDay { last_day, .. } | Absence::Recurring { last_day, .. } => *last_day,
+            _ => self.first_day(),
+        }
+    }
+
+    fn is_recurring(&self) -> bool {
+        matches!(self, Absence::Recurring { .. })
+    }
+
+    /// Whether `day` between first and last day is part of the absence
+    fn covers(&self, day: Day) -> bool {
+        match self {
+            Absence::Recurring {
+                weekday,
+                interval,
+                first_day,
+                except,
+                ..
+            } => {
+                day.weekday() == *weekday
+                    && (day - *first_day).num_weeks() % i64::from(*interval) == 0
+                    && !except.contains(&day)
+            }
+            _ => true,
+        }
+    }
+
+    /// Human readable extent, used when listing absences
+    fn extent(&self) -> String {
+        match self {
+            Absence::Hours { hours, .. } => format!("{hours} h"),
+            Absence::Fraction { fraction, .. } => format!("{fraction} Tag"),
+            Absence::Recurring {
+                weekday, interval, ..
+            } => match interval {
+                1 => format!("jeden {}", weekday_name(*weekday)),
+                _ => format!("alle {interval} Wochen {}", weekday_name(*weekday)),
+            },
+            _ => "ganztägig".to_owned(),
+        }
+    }
+
+    /// Share of the day(s) which is absent, `1.0` for whole days
+    fn share(&self, hours_per_day: &dyn Fn(&Day) -> f32) -> f32 {
+        match self {
+            Absence::SingleDay(_)
+            | Absence::MultiDay { .. }
+            | Absence::WholeDay { .. }
+            | Absence::Recurring { .. } => 1.0,
+            Absence::Hours { day, hours } => hours / hours_per_day(day),
+            Absence::Fraction { fraction, .. } => *fraction,
+        }
+    }
 }
 
 impl IntoIterator for Absence {
@@ -30,13 +424,7 @@ impl IntoIterator for Absence {
     type IntoIter = AbsenceIterator;
 
     fn into_iter(self) -> Self::IntoIter {
-        let index = match self {
-            Absence::SingleDay(day) => day,
-            Absence::MultiDay {
-                first_day,
-                last_day: _,
-            } => first_day,
-        };
+        let index = self.first_day();
         AbsenceIterator {
             absence: self,
             index,
@@ -52,51 +440,495 @@ struct AbsenceIterator {
 impl Iterator for AbsenceIterator {
     type Item = Day;
     fn next(&mut self) -> Option<Day> {
-        let limit = match self.absence {
-            Absence::SingleDay(day) => day,
-            Absence::MultiDay {
-                first_day: _,
-                last_day,
-            } => last_day,
+        let limit = self.absence.last_day();
+
+        while self.index <= limit {
+            let day = self.index;
+            self.index = self.index.succ();
+            if self.absence.covers(day) {
+                return Some(day);
+            }
+        }
+        None
+    }
+}
+
+/// See [AbsenceConfig::days_of_absence]
+pub fn get_days_of_absence(
+    from: Day,
+    to: Day,
+    hours_per_day: &dyn Fn(&Day) -> f32,
+) -> Result<Absences, anyhow::Error> {
+    Ok(AbsenceConfig::load()?.days_of_absence(from, to, hours_per_day))
+}
+
+/// Location of `absence.json` in the platform specific config directory
+fn config_file() -> Option<PathBuf> {
+    use directories::ProjectDirs;
+
+    ProjectDirs::from("org", "Leidingerware", "redmine-helper")
+        .map(|proj_dirs| proj_dirs.config_dir().join("absence.json"))
+}
+
+/// One entry of `absence.json`, as shown by `absence list`
+pub struct ListedAbsence {
+    pub name: String,
+    pub kind: AbsenceKind,
+    pub first_day: Day,
+    pub last_day: Day,
+    pub extent: String,
+}
+
+/// `absence.json` as text, to add or remove entries while keeping all others
+/// (their order and formatting) untouched.
+pub struct AbsenceFile {
+    path: PathBuf,
+    text: String,
+    entries: Map<String, Value>,
+}
+
+impl AbsenceFile {
+    /// Opens `absence.json`, a missing file is treated as empty
+    pub fn open() -> Result<AbsenceFile, anyhow::Error> {
+        let path = config_file().ok_or_else(|| anyhow!("No config directory found"))?;
+        let text = if path.exists() {
+            std::fs::read_to_string(&path).with_context(|| format!("Could not read {path:#?}"))?
+        } else {
+            "{}\n".to_owned()
         };
+        AbsenceFile::from_text(path, text)
+    }
 
-        let result = if self.index <= limit {
-            Some(self.index)
+    fn from_text(path: PathBuf, text: String) -> Result<AbsenceFile, anyhow::Error> {
+        let entries =
+            serde_json::from_str(&text).with_context(|| format!("Could not parse {path:#?}"))?;
+        Ok(AbsenceFile {
+            path,
+            text,
+            entries,
+        })
+    }
+
+    /// Replaces `range` of the text, the result has to be valid JSON again
+    fn splice(&mut self, range: Range<usize>, replacement: &str) -> Result<(), anyhow::Error> {
+        self.text.replace_range(range, replacement);
+        self.entries = serde_json::from_str(&self.text)
+            .with_context(|| format!("Could not edit {:#?}", self.path))?;
+        Ok(())
+    }
+
+    pub fn add(
+        &mut self,
+        name: &str,
+        kind: AbsenceKind,
+        first_day: Day,
+        last_day: Day,
+    ) -> Result<(), anyhow::Error> {
+        if self.entries.contains_key(name) {
+            return Err(anyhow!("There already is an absence named '{name}'"));
+        }
+        if last_day < first_day {
+            return Err(anyhow!(
+                "Last day {last_day} is before first day {first_day}"
+            ));
+        }
+
+        let absence = match (first_day == last_day, kind == AbsenceKind::default()) {
+            (true, true) => Absence::SingleDay(first_day),
+            (true, false) => Absence::WholeDay { day: first_day },
+            (false, _) => Absence::MultiDay {
+                first_day,
+                last_day,
+            },
+        };
+        let entry = if kind == AbsenceKind::default() {
+            Entry::Plain(absence)
         } else {
-            None
+            Entry::Typed { kind, absence }
         };
+        let entry = format!(
+            "{}: {}",
+            Value::from(name),
+            one_line(&serde_json::to_value(entry)?)
+        );
 
-        self.index = self.index.succ();
-        result
+        // the new entry goes last, indented like the one before it
+        let spans = entry_spans(&self.text)?;
+        match spans.last() {
+            Some((_, last)) => {
+                let indent_from = self.text[..last.start].trim_end().len();
+                let indent = self.text[indent_from..last.start].to_owned();
+                self.splice(last.end..last.end, &format!(",{indent}{entry}"))
+            }
+            None => {
+                let inner = object_inner(&self.text)?;
+                self.splice(inner, &format!("\n  {entry}\n"))
+            }
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<(), anyhow::Error> {
+        let spans = entry_spans(&self.text)?;
+        let index = spans
+            .iter()
+            .position(|(key, _)| key == name)
+            .ok_or_else(|| anyhow!("There is no absence named '{name}'"))?;
+
+        // together with the comma, either the one after it or the one before the last entry
+        let range = match (spans.get(index + 1), index.checked_sub(1)) {
+            (Some((_, next)), _) => spans[index].1.start..next.start,
+            (None, Some(previous)) => spans[previous].1.end..spans[index].1.end,
+            (None, None) => object_inner(&self.text)?,
+        };
+        self.splice(range, "")
+    }
+
+    /// Entries touching `year`, or all of them
+    pub fn list(&self, year: Option<i32>) -> Result<Vec<ListedAbsence>, anyhow::Error> {
+        let mut result = Vec::new();
+        for (name, value) in &self.entries {
+            let entry =
+                Entry::deserialize(value).with_context(|| format!("Invalid absence '{name}'"))?;
+            let (kind, absence) = entry.parts();
+            let (first_day, last_day) = (absence.first_day(), absence.last_day());
+
+            if year
+                .into_iter()
+                .all(|year| (first_day.year()..=last_day.year()).contains(&year))
+            {
+                result.push(ListedAbsence {
+                    name: name.clone(),
+                    kind,
+                    first_day,
+                    last_day,
+                    extent: absence.extent(),
+                });
+            }
+        }
+        Ok(result)
+    }
+
+    /// Writes the file, unless any entry is invalid. The old file is only replaced
+    /// once the new one has been written completely.
+    pub fn save(&self) -> Result<(), anyhow::Error> {
+        self.list(None)?;
+
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Could not create config directory {dir:#?}"))?;
+        }
+
+        let temp = self.path.with_extension("json.new");
+        std::fs::write(&temp, &self.text).with_context(|| format!("Could not write {temp:#?}"))?;
+        std::fs::rename(&temp, &self.path)
+            .with_context(|| format!("Could not replace {:#?}", self.path))
     }
 }
 
-pub fn get_days_of_absence(from: Day, to: Day) -> Result<Vec<chrono::NaiveDate>, anyhow::Error> {
-    use directories::ProjectDirs;
+/// Like the examples in the README, e.g. `{"day": "2018-10-17", "hours": 2}`
+fn one_line(value: &Value) -> String {
+    match value {
+        Value::Object(fields) => {
+            let fields = fields
+                .iter()
+                .map(|(key, value)| format!("{}: {}", Value::from(key.as_str()), one_line(value)))
+                .collect::<Vec<_>>();
+            format!("{{{}}}", fields.join(", "))
+        }
+        _ => value.to_string(),
+    }
+}
 
-    // Get config directory in the platform specific default paths
-    if let Some(proj_dirs) = ProjectDirs::from("org", "Leidingerware", "redmine-helper") {
-        let config_file = proj_dirs.config_dir().join("absence.json");
+/// Range between the braces of the top level object
+fn object_inner(text: &str) -> Result<Range<usize>, anyhow::Error> {
+    match (text.find('{'), text.rfind('}')) {
+        (Some(open), Some(close)) if open < close => Ok(open + 1..close),
+        _ => Err(anyhow!("Expected a JSON object")),
+    }
+}
 
-        use std::fs::File;
+/// Name and text range (from the name to the end of the value) of each entry of
+/// the top level object. The text has to be valid JSON.
+fn entry_spans(text: &str) -> Result<Vec<(String, Range<usize>)>, anyhow::Error> {
+    let bytes = text.as_bytes();
+    let invalid = || anyhow!("Expected a JSON object");
+    let at = |i: usize| bytes.get(i).copied().ok_or_else(invalid);
+    let skip_whitespace = |mut i: usize| {
+        while bytes.get(i).is_some_and(u8::is_ascii_whitespace) {
+            i += 1;
+        }
+        i
+    };
+    // index after the closing quote of the string starting at `i`
+    let skip_string = |mut i: usize| -> Result<usize, anyhow::Error> {
+        i += 1;
+        loop {
+            match at(i)? {
+                b'\\' => i += 2,
+                b'"' => return Ok(i + 1),
+                _ => i += 1,
+            }
+        }
+    };
+    let skip_value = |mut i: usize| -> Result<usize, anyhow::Error> {
+        let mut depth = 0;
+        loop {
+            match at(i)? {
+                b'"' => i = skip_string(i)?,
+                b'{' | b'[' => {
+                    depth += 1;
+                    i += 1
+                }
+                b'}' | b']' if depth > 0 => {
+                    depth -= 1;
+                    i += 1
+                }
+                c if depth == 0 && (matches!(c, b',' | b'}' | b']') || c.is_ascii_whitespace()) => {
+                    return Ok(i)
+                }
+                _ => i += 1,
+            }
+        }
+    };
 
-        let file = File::open(&config_file).with_context(|| {
-            format!(
-                "Could not parse days of absence configuration at {config_file:#?}.
-     Please create file; you can check check git repo
-     https://github.com/oleid/redmine-helper for examples."
-            )
-        })?;
-        let absence: AbsenceConfig = serde_json::from_reader(file).with_context(|| {
-            format!("Could not parse days of absence configuration at {config_file:#?}",)
-        })?;
-
-        Ok(absence
-            .to_days()
-            .into_iter()
-            .filter(|day| day >= &from && day <= &to)
-            .collect())
-    } else {
-        Ok(Vec::new())
+    let mut spans = Vec::new();
+    let mut i = skip_whitespace(0);
+    if at(i)? != b'{' {
+        return Err(invalid());
+    }
+    i = skip_whitespace(i + 1);
+    if at(i)? == b'}' {
+        return Ok(spans);
+    }
+    loop {
+        let start = i;
+        i = skip_string(i)?;
+        let name: String = serde_json::from_str(&text[start..i])?;
+        i = skip_whitespace(i);
+        if at(i)? != b':' {
+            return Err(invalid());
+        }
+        let end = skip_value(skip_whitespace(i + 1))?;
+        spans.push((name, start..end));
+        i = skip_whitespace(end);
+        match at(i)? {
+            b',' => i = skip_whitespace(i + 1),
+            b'}' => return Ok(spans),
+            _ => return Err(invalid()),
+        }
     }
 }
+
+#[test]
+fn test_partial_absence() {
+    let absence: AbsenceConfig = serde_json::from_str(
+        r#"{
+        "Urlaub": {"first_day": "2018-10-15", "last_day": "2018-10-16"},
+        "Arzt": {"day": "2018-10-17", "hours": 2},
+        "Halber Tag": {"day": "2018-10-18", "fraction": 0.5},
+        "Nochmal halb": {"day": "2018-10-16", "fraction": 0.5}
+    }"#,
+    )
+    .unwrap();
+
+    let days = absence.to_days(&|_| 8.0);
+    let day = |d| days[&Day::from_ymd(2018, 10, d)][&AbsenceKind::Vacation];
+    assert_eq!(days.len(), 4);
+    assert_eq!(day(15), 1.0);
+    assert_eq!(day(16), 1.0);
+    assert_eq!(day(17), 0.25);
+    assert_eq!(day(18), 0.5);
+}
+
+#[test]
+fn test_absence_kinds() {
+    let absence: AbsenceConfig = serde_json::from_str(
+        r#"{
+        "Grippe": {"kind": "sick", "first_day": "2018-10-15", "last_day": "2018-10-16"},
+        "Gleitzeit": {"kind": "overtime_compensation", "day": "2018-10-17", "hours": 4},
+        "Kind krank": {"kind": "kind_krank", "day": "2018-10-17"},
+        "Urlaub": "2018-10-18"
+    }"#,
+    )
+    .unwrap();
+
+    let days = absence.to_days(&|_| 8.0);
+    assert_eq!(days[&Day::from_ymd(2018, 10, 15)][&AbsenceKind::Sick], 1.0);
+    assert_eq!(
+        days[&Day::from_ymd(2018, 10, 17)].values().sum::<f32>(),
+        1.0
+    );
+    assert_eq!(
+        days[&Day::from_ymd(2018, 10, 18)][&AbsenceKind::Vacation],
+        1.0
+    );
+    assert!(!AbsenceKind::OvertimeCompensation.reduces_target());
+
+    // typos must not count as vacation
+    let typo_in_kind = serde_json::from_str::<AbsenceConfig>(
+        r#"{"Grippe": {"kind": "sik", "day": "2018-10-15"}}"#,
+    );
+    assert!(typo_in_kind.is_err());
+    let typo_in_field =
+        serde_json::from_str::<AbsenceConfig>(r#"{"Zahnarzt": {"day": "2018-10-17", "hour": 2}}"#);
+    assert!(typo_in_field.is_err());
+}
+
+#[test]
+fn test_absence_file() {
+    let mut file = AbsenceFile::from_text(
+        PathBuf::from("absence.json"),
+        r#"{
+    "Zahnarzt":   { "day": "2018-10-17",
+                    "hours": 2 },
+    "Brückentag": "2018-10-02"
+}
+"#
+        .to_owned(),
+    )
+    .unwrap();
+    let day = |m, d| Day::from_ymd(2018, m, d);
+
+    file.add(
+        "Herbsturlaub",
+        AbsenceKind::Vacation,
+        day(10, 22),
+        day(10, 26),
+    )
+    .unwrap();
+    file.add("Grippe", "krank".parse().unwrap(), day(11, 5), day(11, 5))
+        .unwrap();
+    assert!(file
+        .add("Grippe", AbsenceKind::Sick, day(11, 6), day(11, 6))
+        .is_err());
+    assert!(file
+        .add("Rückwärts", AbsenceKind::Sick, day(11, 6), day(11, 5))
+        .is_err());
+
+    file.remove("Brückentag").unwrap();
+    assert!(file.remove("Brückentag").is_err());
+
+    // existing entries keep their place and format
+    assert_eq!(
+        file.text,
+        r#"{
+    "Zahnarzt":   { "day": "2018-10-17",
+                    "hours": 2 },
+    "Herbsturlaub": {"first_day": "2018-10-22", "last_day": "2018-10-26"},
+    "Grippe": {"kind": "sick", "day": "2018-11-05"}
+}
+"#
+    );
+
+    let listed = file.list(Some(2018)).unwrap();
+    assert_eq!(listed.len(), 3);
+    assert_eq!(listed[2].kind, AbsenceKind::Sick);
+    assert!(file.list(Some(2019)).unwrap().is_empty());
+
+    for name in ["Grippe", "Zahnarzt", "Herbsturlaub"] {
+        file.remove(name).unwrap();
+    }
+    assert_eq!(file.text, "{}\n");
+    file.add("Brückentag", AbsenceKind::Vacation, day(10, 2), day(10, 2))
+        .unwrap();
+    assert_eq!(file.text, "{\n  \"Brückentag\": \"2018-10-02\"\n}\n");
+}
+
+#[test]
+fn test_check() {
+    let absence: AbsenceConfig = serde_json::from_str(
+        r#"{
+        "Rückwärts": {"first_day": "2018-10-19", "last_day": "2018-10-15"},
+        "Urlaub": {"first_day": "2018-10-22", "last_day": "2018-10-26"},
+        "Arzt": {"day": "2018-10-24", "hours": 2},
+        "Wochenende": {"first_day": "2018-10-27", "last_day": "2018-10-28"},
+        "Morgens": {"day": "2018-10-29", "fraction": 0.5},
+        "Nachmittags": {"day": "2018-10-29", "fraction": 0.5},
+        "Vertippt": "2081-10-30",
+        "Anderes Jahr": "2017-10-30",
+        "Uralt": "2008-01-30"
+    }"#,
+    )
+    .unwrap();
+
+    let day = |y, m, d| Day::from_ymd(y, m, d);
+    let weekend = |d: &Day| crate::date_helper::is_weekend(*d);
+    let warnings = absence.check(
+        day(2000, 1, 1),
+        day(2100, 1, 1),
+        day(2018, 10, 1),
+        &|_| 8.0,
+        &weekend,
+    );
+
+    assert_eq!(warnings.len(), 5, "{warnings:#?}");
+    assert!(warnings[0].starts_with("'Rückwärts': last day"));
+    assert!(warnings[1].starts_with("'Uralt' is more than 10 years in the past"));
+    assert!(warnings[2].starts_with("'Vertippt' is more than 2 years in the future"));
+    assert!(warnings[3].starts_with("'Wochenende' is only on weekends"));
+    assert_eq!(
+        warnings[4],
+        "'Arzt' and 'Urlaub' overlap on 1 day(s), starting 2018-10-24"
+    );
+    assert_eq!(absence.span(), Some((day(2008, 1, 30), day(2081, 10, 30))));
+}
+
+#[test]
+fn test_recurring_absence() {
+    let absence: AbsenceConfig = serde_json::from_str(
+        r#"{
+        "Teilzeit": {"weekday": "Fr", "first_day": "2018-10-01", "last_day": "2018-11-30",
+                     "except": ["2018-11-16"]},
+        "Jeden zweiten Montag": {"kind": "special_leave", "weekday": "Monday", "interval": 2,
+                                 "first_day": "2018-10-01", "last_day": "2018-10-31"},
+        "Herbsturlaub": {"first_day": "2018-10-22", "last_day": "2018-10-26"}
+    }"#,
+    )
+    .unwrap();
+
+    let days = absence.days_of_absence(
+        Day::from_ymd(2018, 10, 1),
+        Day::from_ymd(2018, 11, 30),
+        &|_| 8.0,
+    );
+    let kinds = |m, d| {
+        days.get(&Day::from_ymd(2018, m, d))
+            .cloned()
+            .unwrap_or_default()
+    };
+
+    let fridays = days
+        .values()
+        .filter(|kinds| kinds.contains_key(&AbsenceKind::DayOff))
+        .count();
+    assert_eq!(fridays, 8);
+    assert!(kinds(11, 16).is_empty());
+    // the friday of the vacation is a day off anyway
+    assert_eq!(kinds(10, 26).get(&AbsenceKind::DayOff), Some(&1.0));
+    assert_eq!(kinds(10, 26).get(&AbsenceKind::Vacation), Some(&0.0));
+
+    let mondays = [1, 8, 15, 22, 29]
+        .iter()
+        .filter(|d| kinds(10, **d).contains_key(&AbsenceKind::SpecialLeave))
+        .count();
+    assert_eq!(mondays, 3);
+
+    let zero_interval = serde_json::from_str::<AbsenceConfig>(
+        r#"{"Teilzeit": {"weekday": "Fr", "interval": 0, "first_day": "2018-10-01", "last_day": "2018-12-31"}}"#,
+    );
+    assert!(zero_interval.is_err());
+}
+
+#[test]
+fn test_absence_booking() {
+    let bookings: Vec<AbsenceBooking> = serde_json::from_str(
+        r#"[{"project": 42, "activity": 9, "kind": "sick"}, {"project": 42}]"#,
+    )
+    .unwrap();
+
+    assert!(bookings[0].matches(42, 9));
+    assert!(!bookings[0].matches(42, 10));
+    assert!(bookings[1].matches(42, 10));
+    assert!(!bookings[1].matches(43, 9));
+    assert_eq!(bookings[1].kind, AbsenceKind::Vacation);
+}
@@ -0,0 +1,71 @@
+use anyhow::anyhow;
+
+/// Delimiter and decimal separator of csv output. German Excel needs `;` and `,`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CsvFormat {
+    pub delimiter: char,
+    pub decimal_separator: char,
+}
+
+impl Default for CsvFormat {
+    fn default() -> Self {
+        CsvFormat {
+            delimiter: ',',
+            decimal_separator: '.',
+        }
+    }
+}
+
+/// Single character from the command line, `tab` for a tabulator
+pub fn parse_separator(value: &str) -> Result<char, anyhow::Error> {
+    if value == "tab" {
+        return Ok('\t');
+    }
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => Err(anyhow!(
+            "Expected a single character as separator, got '{value}'"
+        )),
+    }
+}
+
+impl CsvFormat {
+    pub fn number(&self, val: f32) -> String {
+        format!("{:.2}", val).replace('.', &self.decimal_separator.to_string())
+    }
+
+    /// Quotes the field if needed
+    pub fn field(&self, text: &str) -> String {
+        if text.contains(self.delimiter) || text.contains(['"', '\n', '\r']) {
+            format!("\"{}\"", text.replace('"', "\"\""))
+        } else {
+            text.to_owned()
+        }
+    }
+
+    /// One line including the line break, fields are quoted as needed
+    pub fn line<S: AsRef<str>>(&self, fields: &[S]) -> String {
+        let fields = fields
+            .iter()
+            .map(|field| self.field(field.as_ref()))
+            .collect::<Vec<_>>();
+        format!("{}\r\n", fields.join(&self.delimiter.to_string()))
+    }
+}
+
+#[test]
+fn test_csv() {
+    let german = CsvFormat {
+        delimiter: ';',
+        decimal_separator: ',',
+    };
+    assert_eq!(german.number(-1.5), "-1,50");
+    assert_eq!(
+        german.line(&["a;b", "say \"hi\"", "1,5"]),
+        "\"a;b\";\"say \"\"hi\"\"\";1,5\r\n"
+    );
+    assert_eq!(CsvFormat::default().line(&["1,5", "x"]), "\"1,5\",x\r\n");
+    assert_eq!(parse_separator("tab").unwrap(), '\t');
+    assert!(parse_separator(";;").is_err());
+}
@@ -1,25 +1,41 @@
+use anyhow::anyhow;
 use chrono::{Datelike, Duration, NaiveDate, Weekday};
 use std::ops::{Add, Range};
 
-pub fn count_weekdays(
-    from: NaiveDate,
-    to: NaiveDate,
-    condition: &dyn Fn(&NaiveDate) -> bool,
-) -> usize {
-    let mut cur = from.clone();
-    let mut count = 0;
-
-    while cur != to {
-        if cur.weekday() != chrono::Weekday::Sat
-            && cur.weekday() != chrono::Weekday::Sun
-            && condition(&cur)
-        {
-            count += 1;
-        }
-        cur = cur.succ();
+pub fn is_weekend(date: NaiveDate) -> bool {
+    date.weekday() == Weekday::Sat || date.weekday() == Weekday::Sun
+}
+
+pub fn weekday_name(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "Mo",
+        Weekday::Tue => "Di",
+        Weekday::Wed => "Mi",
+        Weekday::Thu => "Do",
+        Weekday::Fri => "Fr",
+        Weekday::Sat => "Sa",
+        Weekday::Sun => "So",
     }
+}
 
-    count
+/// Accepts german (`Fr`, `Freitag`) as well as english (`Fri`, `Friday`) names
+pub fn parse_weekday(name: &str) -> Option<Weekday> {
+    const GERMAN: [(&str, Weekday); 7] = [
+        ("montag", Weekday::Mon),
+        ("dienstag", Weekday::Tue),
+        ("mittwoch", Weekday::Wed),
+        ("donnerstag", Weekday::Thu),
+        ("freitag", Weekday::Fri),
+        ("samstag", Weekday::Sat),
+        ("sonntag", Weekday::Sun),
+    ];
+    let name = name.trim().to_lowercase();
+
+    GERMAN
+        .iter()
+        .find(|(german, _)| *german == name || german[..2] == name)
+        .map(|(_, weekday)| *weekday)
+        .or_else(|| name.parse().ok())
 }
 
 pub fn next_month(date: NaiveDate) -> NaiveDate {
@@ -51,6 +67,85 @@ pub fn today() -> NaiveDate {
     chrono::Local::today().naive_local()
 }
 
+/// Length of the periods in the report
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Grouping {
+    /// Months, then weeks, then the remaining days
+    #[default]
+    Auto,
+    Day,
+    /// ISO weeks starting on monday
+    Week,
+    Month,
+    Quarter,
+    Year,
+}
+
+impl std::str::FromStr for Grouping {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Grouping::Auto),
+            "day" => Ok(Grouping::Day),
+            "week" => Ok(Grouping::Week),
+            "month" => Ok(Grouping::Month),
+            "quarter" => Ok(Grouping::Quarter),
+            "year" => Ok(Grouping::Year),
+            _ => Err(anyhow!("Unknown grouping '{s}'")),
+        }
+    }
+}
+
+/// Start of the calendar period following the one containing `date`.
+/// `Grouping::Auto` has no calendar periods, the next month is used.
+pub fn next_period(date: NaiveDate, grouping: Grouping) -> NaiveDate {
+    match grouping {
+        Grouping::Day => date.succ(),
+        Grouping::Week => next_week_monday(date),
+        Grouping::Month | Grouping::Auto => next_month(date),
+        Grouping::Quarter => {
+            let month = (date.month0() / 3) * 3 + 4;
+            if month > 12 {
+                NaiveDate::from_ymd(date.year() + 1, month - 12, 1)
+            } else {
+                NaiveDate::from_ymd(date.year(), month, 1)
+            }
+        }
+        Grouping::Year => NaiveDate::from_ymd(date.year() + 1, 1, 1),
+    }
+}
+
+/// Periods of the report in `from..=to`, the end of each period is excluded
+pub fn periods(from: NaiveDate, to: NaiveDate, grouping: Grouping) -> Vec<(NaiveDate, NaiveDate)> {
+    let mut cur = from;
+    let mut dates = Vec::new();
+
+    if grouping != Grouping::Auto {
+        // calendar periods, the first and last one might be cut off
+        while cur <= to {
+            let next = next_period(cur, grouping).min(to.succ());
+            dates.push((cur, next));
+            cur = next;
+        }
+        return dates;
+    }
+
+    while cur < to {
+        cur = if next_month(cur) < to {
+            dates.push((cur, next_month(cur)));
+            next_month(cur)
+        } else if next_week_monday(cur) < to {
+            dates.push((cur, next_week_monday(cur)));
+            next_week_monday(cur)
+        } else {
+            dates.push((cur, to.succ()));
+            to.succ()
+        };
+    }
+    dates
+}
+
 pub fn years_in_range(from: NaiveDate, to: NaiveDate) -> Range<i32> {
     Range {
         start: from.year(),
@@ -64,3 +159,60 @@ fn test_add_week() {
     let b = NaiveDate::from_ymd(2022, 2, 21);
     assert_eq!(next_week_monday(a), b);
 }
+
+#[test]
+fn test_next_period() {
+    let day = |y, m, d| NaiveDate::from_ymd(y, m, d);
+    assert_eq!(
+        next_period(day(2018, 10, 29), Grouping::Day),
+        day(2018, 10, 30)
+    );
+    assert_eq!(
+        next_period(day(2018, 12, 31), Grouping::Week),
+        day(2019, 1, 7)
+    );
+    assert_eq!(
+        next_period(day(2018, 10, 29), Grouping::Month),
+        day(2018, 11, 1)
+    );
+    assert_eq!(
+        next_period(day(2018, 3, 31), Grouping::Quarter),
+        day(2018, 4, 1)
+    );
+    assert_eq!(
+        next_period(day(2018, 11, 15), Grouping::Quarter),
+        day(2019, 1, 1)
+    );
+    assert_eq!(
+        next_period(day(2018, 1, 1), Grouping::Year),
+        day(2019, 1, 1)
+    );
+}
+
+#[test]
+fn test_periods() {
+    let day = |y, m, d| NaiveDate::from_ymd(y, m, d);
+    assert_eq!(
+        periods(day(2018, 2, 15), day(2018, 8, 10), Grouping::Quarter),
+        vec![
+            (day(2018, 2, 15), day(2018, 4, 1)),
+            (day(2018, 4, 1), day(2018, 7, 1)),
+            (day(2018, 7, 1), day(2018, 8, 11)),
+        ]
+    );
+    assert_eq!(
+        periods(day(2018, 12, 27), day(2019, 1, 2), Grouping::Week),
+        vec![
+            (day(2018, 12, 27), day(2018, 12, 31)),
+            (day(2018, 12, 31), day(2019, 1, 3)),
+        ]
+    );
+    assert_eq!(
+        periods(day(2018, 10, 1), day(2018, 10, 31), Grouping::Day).len(),
+        31
+    );
+    // without grouping, a month ending at `to` is split into weeks
+    let auto = periods(day(2018, 10, 1), day(2018, 10, 31), Grouping::Auto);
+    assert_eq!(auto.len(), 5);
+    assert_eq!(auto[4], (day(2018, 10, 29), day(2018, 11, 1)));
+}
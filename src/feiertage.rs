@@ -1,5 +1,9 @@
+use crate::holidays::{normalize_name, DayOff, HolidayProvider, Holidays};
 use anyhow::{anyhow, Context};
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
 use std::collections::btree_map::BTreeMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
 
 #[derive(Deserialize, Debug)]
 struct ApiResponse {
@@ -8,14 +12,26 @@ struct ApiResponse {
 }
 
 #[derive(Deserialize, Debug)]
-#[allow(unused)]
 struct HolidayInfo {
-    datum: chrono::NaiveDate,
+    datum: NaiveDate,
     hinweis: String,
 }
 
-#[derive(Debug)]
-#[allow(dead_code)]
+impl ApiResponse {
+    fn into_holidays(self) -> Holidays {
+        self.inner
+            .into_iter()
+            .map(|(name, info)| {
+                (
+                    info.datum,
+                    DayOff::whole_day(&name).with_note(&info.hinweis),
+                )
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Bundesland {
     BE, // Berlin
     BB, // 	Brandenburg (Potsdam)
@@ -35,12 +51,223 @@ pub enum Bundesland {
     TH, //	Thüringen (Erfurt)
 }
 
+impl Bundesland {
+    pub const ALL: [Bundesland; 16] = [
+        Bundesland::BE,
+        Bundesland::BB,
+        Bundesland::BW,
+        Bundesland::BY,
+        Bundesland::HB,
+        Bundesland::HH,
+        Bundesland::HE,
+        Bundesland::MV,
+        Bundesland::NI,
+        Bundesland::NW,
+        Bundesland::RP,
+        Bundesland::SH,
+        Bundesland::SL,
+        Bundesland::SN,
+        Bundesland::ST,
+        Bundesland::TH,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Bundesland::BE => "Berlin",
+            Bundesland::BB => "Brandenburg",
+            Bundesland::BW => "Baden-Württemberg",
+            Bundesland::BY => "Bayern",
+            Bundesland::HB => "Bremen",
+            Bundesland::HH => "Hamburg",
+            Bundesland::HE => "Hessen",
+            Bundesland::MV => "Mecklenburg-Vorpommern",
+            Bundesland::NI => "Niedersachsen",
+            Bundesland::NW => "Nordrhein-Westfalen",
+            Bundesland::RP => "Rheinland-Pfalz",
+            Bundesland::SH => "Schleswig-Holstein",
+            Bundesland::SL => "Saarland",
+            Bundesland::SN => "Sachsen",
+            Bundesland::ST => "Sachsen-Anhalt",
+            Bundesland::TH => "Thüringen",
+        }
+    }
+}
+
+impl std::fmt::Display for Bundesland {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} ({:?})", self.name(), self)
+    }
+}
+
+impl std::str::FromStr for Bundesland {
+    type Err = anyhow::Error;
+
+    /// Accepts the abbreviation (`NW`, also `NRW`) as well as the full name,
+    /// case insensitive and with or without umlauts.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let wanted = normalize_name(s);
+
+        if wanted == "nrw" {
+            return Ok(Bundesland::NW);
+        }
+
+        Bundesland::ALL
+            .iter()
+            .find(|state| {
+                normalize_name(&format!("{:?}", state)) == wanted
+                    || normalize_name(state.name()) == wanted
+            })
+            .copied()
+            .ok_or_else(|| anyhow!("Unknown Bundesland '{}'", s))
+    }
+}
+
+impl HolidayProvider for Bundesland {
+    fn holidays(&self, year: i32) -> Result<Holidays, anyhow::Error> {
+        Ok(compute_holidays(year, *self))
+    }
+}
+
+/// Computes the public holidays of `state` without asking feiertage-api.de.
+///
+/// The names match those of the API, so both results can be compared.
+pub fn compute_holidays(year: i32, state: Bundesland) -> Holidays {
+    use Bundesland::*;
+
+    let fixed = |month, day| NaiveDate::from_ymd(year, month, day);
+    let easter = easter_sunday(year);
+    let after_easter = |days| easter + Duration::days(days);
+    let in_states = |states: &[Bundesland]| states.contains(&state);
+
+    // Wednesday before the 23rd of November
+    let buss_und_bettag = {
+        let mut day = fixed(11, 22);
+        while day.weekday() != Weekday::Wed {
+            day = day.pred();
+        }
+        day
+    };
+
+    let reformationstag = in_states(&[BB, MV, SN, ST, TH])
+        || year == 2017
+        || (year >= 2018 && in_states(&[HB, HH, NI, SH]));
+
+    let candidates = [
+        ("Neujahrstag", fixed(1, 1), true),
+        ("Heilige Drei Könige", fixed(1, 6), in_states(&[BW, BY, ST])),
+        (
+            "Frauentag",
+            fixed(3, 8),
+            (state == BE && year >= 2019) || (state == MV && year >= 2023),
+        ),
+        ("Karfreitag", after_easter(-2), true),
+        ("Ostersonntag", easter, state == BB),
+        ("Ostermontag", after_easter(1), true),
+        ("Tag der Arbeit", fixed(5, 1), true),
+        (
+            "Tag der Befreiung",
+            fixed(5, 8),
+            state == BE && (year == 2020 || year == 2025),
+        ),
+        ("Christi Himmelfahrt", after_easter(39), true),
+        ("Pfingstsonntag", after_easter(49), state == BB),
+        ("Pfingstmontag", after_easter(50), true),
+        (
+            "Fronleichnam",
+            after_easter(60),
+            in_states(&[BW, BY, HE, NW, RP, SL, SN, TH]),
+        ),
+        ("Augsburger Friedensfest", fixed(8, 8), state == BY),
+        ("Mariä Himmelfahrt", fixed(8, 15), in_states(&[BY, SL])),
+        ("Weltkindertag", fixed(9, 20), state == TH && year >= 2019),
+        ("Tag der Deutschen Einheit", fixed(10, 3), true),
+        ("Reformationstag", fixed(10, 31), reformationstag),
+        (
+            "Allerheiligen",
+            fixed(11, 1),
+            in_states(&[BW, BY, NW, RP, SL]),
+        ),
+        ("Buß- und Bettag", buss_und_bettag, state == SN),
+        ("1. Weihnachtstag", fixed(12, 25), true),
+        ("2. Weihnachtstag", fixed(12, 26), true),
+    ];
+
+    candidates
+        .iter()
+        .filter(|(_, _, applies)| *applies)
+        .map(|(name, date, _)| {
+            (
+                *date,
+                DayOff::whole_day(name).with_note(regional_note(name, state)),
+            )
+        })
+        .collect()
+}
+
+/// Restriction of holidays, which are only observed in parts of the state
+fn regional_note(name: &str, state: Bundesland) -> &'static str {
+    match (name, state) {
+        ("Fronleichnam", Bundesland::SN) => {
+            "Nur in einigen katholisch geprägten Gemeinden im Landkreis Bautzen"
+        }
+        ("Fronleichnam", Bundesland::TH) => {
+            "Nur im Landkreis Eichsfeld und in einigen Gemeinden im Unstrut-Hainich-Kreis und Wartburgkreis"
+        }
+        ("Augsburger Friedensfest", Bundesland::BY) => "Nur im Stadtgebiet Augsburg",
+        ("Mariä Himmelfahrt", Bundesland::BY) => {
+            "Nur in Gemeinden mit überwiegend katholischer Bevölkerung"
+        }
+        _ => "",
+    }
+}
+
+/// Gregorian easter sunday, using the anonymous gregorian algorithm (Meeus/Jones/Butcher)
+pub fn easter_sunday(year: i32) -> NaiveDate {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+
+    NaiveDate::from_ymd(year, month as u32, day as u32)
+}
+
+/// Human readable differences between computed holidays and those from the API
+pub fn differences(computed: &Holidays, online: &Holidays) -> Vec<String> {
+    let mut result = Vec::new();
+    for (date, holiday) in online {
+        if !computed.contains_key(date) {
+            result.push(format!(
+                "{} ({date}) is missing in the computed holidays",
+                holiday.name
+            ));
+        }
+    }
+    for (date, holiday) in computed {
+        if !online.contains_key(date) {
+            result.push(format!(
+                "{} ({date}) is not known to feiertage-api.de",
+                holiday.name
+            ));
+        }
+    }
+    result
+}
+
 pub async fn get_holidays(
     year: i32,
     state: Bundesland,
-) -> Result<BTreeMap<chrono::NaiveDate, String>, anyhow::Error> {
-    let client = reqwest::Client::new();
-
+    client: &reqwest::Client,
+) -> Result<Holidays, anyhow::Error> {
     let url = format!(
         "https://feiertage-api.de/api/?jahr={}&nur_land={:?}",
         year, state
@@ -58,16 +285,68 @@ pub async fn get_holidays(
 
     let response = req.json::<ApiResponse>().await?;
 
-    Ok(response
-        .inner
-        .into_iter()
-        .map(|(k, v)| (v.datum, k.into()))
-        .collect())
+    Ok(response.into_holidays())
 }
 
-#[test]
-fn test_feiertage() {
-    let data = r#"{
+/// Like [get_holidays], but keeps the result in the cache directory, since holidays
+/// don't change. Falls back to the cached list if the API can't be reached.
+pub async fn get_holidays_cached(
+    year: i32,
+    state: Bundesland,
+    client: &reqwest::Client,
+    refresh: bool,
+) -> Result<Holidays, anyhow::Error> {
+    let cache_file = cache_file_for(year, state);
+    let cached = cache_file.as_deref().and_then(read_cache);
+
+    if let (Some(holidays), false) = (&cached, refresh) {
+        return Ok(holidays.clone());
+    }
+
+    match get_holidays(year, state, client).await {
+        Ok(holidays) => {
+            if let Some(cache_file) = &cache_file {
+                write_cache(cache_file, &holidays).unwrap_or_else(|e| {
+                    eprintln!("Warning: could not cache holidays at {cache_file:#?}: {e:#}")
+                });
+            }
+            Ok(holidays)
+        }
+        Err(e) => match cached {
+            Some(holidays) => {
+                eprintln!("Warning: using cached holidays for {year}: {e:#}");
+                Ok(holidays)
+            }
+            None => Err(e),
+        },
+    }
+}
+
+fn cache_file_for(year: i32, state: Bundesland) -> Option<PathBuf> {
+    use directories::ProjectDirs;
+
+    ProjectDirs::from("org", "Leidingerware", "redmine-helper").map(|proj_dirs| {
+        proj_dirs
+            .cache_dir()
+            .join(format!("feiertage-{year}-{state:?}.json"))
+    })
+}
+
+fn read_cache(cache_file: &Path) -> Option<Holidays> {
+    let file = File::open(cache_file).ok()?;
+    serde_json::from_reader(file).ok()
+}
+
+fn write_cache(cache_file: &Path, holidays: &Holidays) -> Result<(), anyhow::Error> {
+    if let Some(dir) = cache_file.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    serde_json::to_writer_pretty(File::create(cache_file)?, holidays)?;
+    Ok(())
+}
+
+#[cfg(test)]
+const TEST_DATA_NW_2018: &str = r#"{
    "Neujahrstag":{
       "datum":"2018-01-01",
       "hinweis":""
@@ -114,5 +393,62 @@ fn test_feiertage() {
    }
 }"#;
 
-    let _r: ApiResponse = serde_json::from_str(data).unwrap();
+#[test]
+fn test_feiertage() {
+    let _r: ApiResponse = serde_json::from_str(TEST_DATA_NW_2018).unwrap();
+}
+
+#[test]
+fn test_compute_holidays_matches_api() {
+    let api: ApiResponse = serde_json::from_str(TEST_DATA_NW_2018).unwrap();
+
+    assert_eq!(compute_holidays(2018, Bundesland::NW), api.into_holidays());
+}
+
+#[test]
+fn test_easter_sunday() {
+    assert_eq!(easter_sunday(2018), NaiveDate::from_ymd(2018, 4, 1));
+    assert_eq!(easter_sunday(2019), NaiveDate::from_ymd(2019, 4, 21));
+    assert_eq!(easter_sunday(2024), NaiveDate::from_ymd(2024, 3, 31));
+    assert_eq!(easter_sunday(2038), NaiveDate::from_ymd(2038, 4, 25));
+}
+
+#[test]
+fn test_regional_holidays() {
+    let sn = compute_holidays(2018, Bundesland::SN);
+    assert_eq!(
+        sn.get(&NaiveDate::from_ymd(2018, 11, 21)).unwrap().name,
+        "Buß- und Bettag"
+    );
+    assert!(sn
+        .get(&NaiveDate::from_ymd(2018, 5, 31))
+        .unwrap()
+        .note
+        .is_some());
+    assert!(sn.contains_key(&NaiveDate::from_ymd(2018, 10, 31)));
+
+    // Reformationstag became a holiday in northern germany in 2018, nationwide in 2017 only
+    assert!(
+        !compute_holidays(2016, Bundesland::NI).contains_key(&NaiveDate::from_ymd(2016, 10, 31))
+    );
+    assert!(compute_holidays(2017, Bundesland::NW).contains_key(&NaiveDate::from_ymd(2017, 10, 31)));
+    assert!(compute_holidays(2018, Bundesland::NI).contains_key(&NaiveDate::from_ymd(2018, 10, 31)));
+
+    assert!(compute_holidays(2019, Bundesland::TH).contains_key(&NaiveDate::from_ymd(2019, 9, 20)));
+    assert!(!compute_holidays(2018, Bundesland::TH).contains_key(&NaiveDate::from_ymd(2018, 9, 20)));
+    assert!(compute_holidays(2019, Bundesland::BE).contains_key(&NaiveDate::from_ymd(2019, 3, 8)));
+    assert!(!compute_holidays(2018, Bundesland::BE).contains_key(&NaiveDate::from_ymd(2018, 3, 8)));
+}
+
+#[test]
+fn test_parse_bundesland() {
+    assert_eq!("NW".parse::<Bundesland>().unwrap(), Bundesland::NW);
+    assert_eq!("nrw".parse::<Bundesland>().unwrap(), Bundesland::NW);
+    assert_eq!("Bayern".parse::<Bundesland>().unwrap(), Bundesland::BY);
+    assert_eq!("thueringen".parse::<Bundesland>().unwrap(), Bundesland::TH);
+    assert_eq!(
+        "Baden-Württemberg".parse::<Bundesland>().unwrap(),
+        Bundesland::BW
+    );
+    assert!("Bavaria".parse::<Bundesland>().is_err());
 }
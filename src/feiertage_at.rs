@@ -0,0 +1,46 @@
+use crate::feiertage::easter_sunday;
+use crate::holidays::{DayOff, HolidayProvider, Holidays};
+use chrono::{Duration, NaiveDate};
+
+/// Public holidays in Austria. They are the same in all states, the days of the
+/// patron saints of the states are no general days off.
+pub struct Oesterreich;
+
+impl HolidayProvider for Oesterreich {
+    fn holidays(&self, year: i32) -> Result<Holidays, anyhow::Error> {
+        let fixed = |month, day| NaiveDate::from_ymd(year, month, day);
+        let easter = easter_sunday(year);
+        let after_easter = |days| easter + Duration::days(days);
+
+        let holidays = [
+            ("Neujahr", fixed(1, 1)),
+            ("Heilige Drei Könige", fixed(1, 6)),
+            ("Ostermontag", after_easter(1)),
+            ("Staatsfeiertag", fixed(5, 1)),
+            ("Christi Himmelfahrt", after_easter(39)),
+            ("Pfingstmontag", after_easter(50)),
+            ("Fronleichnam", after_easter(60)),
+            ("Mariä Himmelfahrt", fixed(8, 15)),
+            ("Nationalfeiertag", fixed(10, 26)),
+            ("Allerheiligen", fixed(11, 1)),
+            ("Mariä Empfängnis", fixed(12, 8)),
+            ("Christtag", fixed(12, 25)),
+            ("Stefanitag", fixed(12, 26)),
+        ];
+
+        Ok(holidays
+            .iter()
+            .map(|(name, date)| (*date, DayOff::whole_day(name)))
+            .collect())
+    }
+}
+
+#[test]
+fn test_feiertage_at() {
+    let holidays = Oesterreich.holidays(2023).unwrap();
+    assert_eq!(holidays.len(), 13);
+    assert_eq!(
+        holidays.get(&NaiveDate::from_ymd(2023, 6, 8)).unwrap().name,
+        "Fronleichnam"
+    );
+}
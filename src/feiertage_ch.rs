@@ -0,0 +1,264 @@
+use crate::feiertage::easter_sunday;
+use crate::holidays::{normalize_name, DayOff, HolidayProvider, Holidays};
+use anyhow::anyhow;
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kanton {
+    ZH, // Zürich
+    BE, // Bern
+    LU, // Luzern
+    UR, // Uri
+    SZ, // Schwyz
+    OW, // Obwalden
+    NW, // Nidwalden
+    GL, // Glarus
+    ZG, // Zug
+    FR, // Freiburg
+    SO, // Solothurn
+    BS, // Basel-Stadt
+    BL, // Basel-Landschaft
+    SH, // Schaffhausen
+    AR, // Appenzell Ausserrhoden
+    AI, // Appenzell Innerrhoden
+    SG, // St. Gallen
+    GR, // Graubünden
+    AG, // Aargau
+    TG, // Thurgau
+    TI, // Tessin
+    VD, // Waadt
+    VS, // Wallis
+    NE, // Neuenburg
+    GE, // Genf
+    JU, // Jura
+}
+
+impl Kanton {
+    pub const ALL: [Kanton; 26] = [
+        Kanton::ZH,
+        Kanton::BE,
+        Kanton::LU,
+        Kanton::UR,
+        Kanton::SZ,
+        Kanton::OW,
+        Kanton::NW,
+        Kanton::GL,
+        Kanton::ZG,
+        Kanton::FR,
+        Kanton::SO,
+        Kanton::BS,
+        Kanton::BL,
+        Kanton::SH,
+        Kanton::AR,
+        Kanton::AI,
+        Kanton::SG,
+        Kanton::GR,
+        Kanton::AG,
+        Kanton::TG,
+        Kanton::TI,
+        Kanton::VD,
+        Kanton::VS,
+        Kanton::NE,
+        Kanton::GE,
+        Kanton::JU,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Kanton::ZH => "Zürich",
+            Kanton::BE => "Bern",
+            Kanton::LU => "Luzern",
+            Kanton::UR => "Uri",
+            Kanton::SZ => "Schwyz",
+            Kanton::OW => "Obwalden",
+            Kanton::NW => "Nidwalden",
+            Kanton::GL => "Glarus",
+            Kanton::ZG => "Zug",
+            Kanton::FR => "Freiburg",
+            Kanton::SO => "Solothurn",
+            Kanton::BS => "Basel-Stadt",
+            Kanton::BL => "Basel-Landschaft",
+            Kanton::SH => "Schaffhausen",
+            Kanton::AR => "Appenzell Ausserrhoden",
+            Kanton::AI => "Appenzell Innerrhoden",
+            Kanton::SG => "St. Gallen",
+            Kanton::GR => "Graubünden",
+            Kanton::AG => "Aargau",
+            Kanton::TG => "Thurgau",
+            Kanton::TI => "Tessin",
+            Kanton::VD => "Waadt",
+            Kanton::VS => "Wallis",
+            Kanton::NE => "Neuenburg",
+            Kanton::GE => "Genf",
+            Kanton::JU => "Jura",
+        }
+    }
+}
+
+impl std::fmt::Display for Kanton {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} ({:?})", self.name(), self)
+    }
+}
+
+impl std::str::FromStr for Kanton {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let wanted = normalize_name(s);
+
+        Kanton::ALL
+            .iter()
+            .find(|kanton| {
+                normalize_name(&format!("{:?}", kanton)) == wanted
+                    || normalize_name(kanton.name()) == wanted
+            })
+            .copied()
+            .ok_or_else(|| anyhow!("Unknown Kanton '{}'", s))
+    }
+}
+
+/// First `weekday` in the given month
+fn first_weekday_of(year: i32, month: u32, weekday: Weekday) -> NaiveDate {
+    let mut day = NaiveDate::from_ymd(year, month, 1);
+    while day.weekday() != weekday {
+        day = day.succ();
+    }
+    day
+}
+
+impl HolidayProvider for Kanton {
+    /// Holidays which are by law or customarily days off in the canton. Holidays only
+    /// observed in some municipalities or only in the afternoon are not included.
+    fn holidays(&self, year: i32) -> Result<Holidays, anyhow::Error> {
+        use Kanton::*;
+
+        let kanton = *self;
+        let fixed = |month, day| NaiveDate::from_ymd(year, month, day);
+        let easter = easter_sunday(year);
+        let after_easter = |days| easter + Duration::days(days);
+        let in_cantons = |cantons: &[Kanton]| cantons.contains(&kanton);
+
+        let catholic = [LU, UR, SZ, OW, NW, ZG, FR, SO, AI, AG, TI, VS, JU];
+
+        // Thursday after the first sunday in september
+        let jeune_genevois = first_weekday_of(year, 9, Weekday::Sun) + Duration::days(4);
+        // Monday after the third sunday in september
+        let lundi_du_jeune = first_weekday_of(year, 9, Weekday::Sun) + Duration::days(15);
+        // first thursday in april, a week later if that's Maundy Thursday or in Easter week
+        let naefelser_fahrt = match first_weekday_of(year, 4, Weekday::Thu) {
+            day if (after_easter(-3)..=after_easter(4)).contains(&day) => day + Duration::days(7),
+            day => day,
+        };
+
+        let candidates = [
+            ("Neujahrstag", fixed(1, 1), true),
+            (
+                "Berchtoldstag",
+                fixed(1, 2),
+                in_cantons(&[ZH, BE, LU, OW, GL, ZG, FR, SO, SH, TG, VD, NE, JU, AG]),
+            ),
+            (
+                "Heilige Drei Könige",
+                fixed(1, 6),
+                in_cantons(&[UR, SZ, TI]),
+            ),
+            (
+                "Jahrestag der Ausrufung der Republik",
+                fixed(3, 1),
+                kanton == NE,
+            ),
+            ("Josefstag", fixed(3, 19), in_cantons(&[UR, SZ, NW, TI, VS])),
+            ("Näfelser Fahrt", naefelser_fahrt, kanton == GL),
+            ("Karfreitag", after_easter(-2), !in_cantons(&[TI, VS])),
+            ("Ostermontag", after_easter(1), kanton != VS),
+            (
+                "Tag der Arbeit",
+                fixed(5, 1),
+                in_cantons(&[ZH, BS, BL, SH, TG, TI, NE, JU]),
+            ),
+            ("Auffahrt", after_easter(39), true),
+            ("Pfingstmontag", after_easter(50), kanton != VS),
+            ("Fronleichnam", after_easter(60), in_cantons(&catholic)),
+            ("Fest der Unabhängigkeit", fixed(6, 23), kanton == JU),
+            ("Bundesfeier", fixed(8, 1), true),
+            ("Mariä Himmelfahrt", fixed(8, 15), in_cantons(&catholic)),
+            ("Genfer Bettag", jeune_genevois, kanton == GE),
+            ("Bettagsmontag", lundi_du_jeune, kanton == VD),
+            (
+                "Allerheiligen",
+                fixed(11, 1),
+                in_cantons(&catholic) || in_cantons(&[GL, SG]),
+            ),
+            (
+                "Mariä Empfängnis",
+                fixed(12, 8),
+                in_cantons(&[LU, UR, SZ, OW, NW, ZG, FR, AI, AG, TI, VS]),
+            ),
+            ("Weihnachtstag", fixed(12, 25), true),
+            (
+                "Stephanstag",
+                fixed(12, 26),
+                !in_cantons(&[VD, VS, NE, GE, JU]),
+            ),
+            (
+                "Wiederherstellung der Republik",
+                fixed(12, 31),
+                kanton == GE,
+            ),
+        ];
+
+        Ok(candidates
+            .iter()
+            .filter(|(_, _, applies)| *applies)
+            .map(|(name, date, _)| (*date, DayOff::whole_day(name)))
+            .collect())
+    }
+}
+
+#[test]
+fn test_feiertage_ch() {
+    let zh = Kanton::ZH.holidays(2024).unwrap();
+    assert_eq!(
+        zh.values().map(|h| h.name.as_str()).collect::<Vec<_>>(),
+        vec![
+            "Neujahrstag",
+            "Berchtoldstag",
+            "Karfreitag",
+            "Ostermontag",
+            "Tag der Arbeit",
+            "Auffahrt",
+            "Pfingstmontag",
+            "Bundesfeier",
+            "Weihnachtstag",
+            "Stephanstag"
+        ]
+    );
+
+    let ge = Kanton::GE.holidays(2024).unwrap();
+    assert_eq!(
+        ge.get(&NaiveDate::from_ymd(2024, 9, 5)).unwrap().name,
+        "Genfer Bettag"
+    );
+    let vd = Kanton::VD.holidays(2024).unwrap();
+    assert_eq!(
+        vd.get(&NaiveDate::from_ymd(2024, 9, 16)).unwrap().name,
+        "Bettagsmontag"
+    );
+}
+
+#[test]
+fn test_naefelser_fahrt() {
+    let fahrt = |year| {
+        Kanton::GL
+            .holidays(year)
+            .unwrap()
+            .into_iter()
+            .find(|(_, holiday)| holiday.name == "Näfelser Fahrt")
+            .map(|(day, _)| day)
+            .unwrap()
+    };
+    assert_eq!(fahrt(2018), NaiveDate::from_ymd(2018, 4, 12));
+    assert_eq!(fahrt(2021), NaiveDate::from_ymd(2021, 4, 8));
+    assert_eq!(fahrt(2019), NaiveDate::from_ymd(2019, 4, 4));
+}
@@ -0,0 +1,321 @@
+use crate::feiertage::Bundesland;
+use crate::feiertage_ch::Kanton;
+use anyhow::{anyhow, Context};
+use chrono::{Datelike, NaiveDate};
+use std::collections::btree_map::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// A holiday, closure day or partially free day
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DayOff {
+    pub name: String,
+    /// Share of the day which is off, `1.0` for a whole day
+    pub fraction: f32,
+    /// Restrictions, e.g. if the holiday only applies in some municipalities
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+impl DayOff {
+    pub fn whole_day(name: &str) -> DayOff {
+        DayOff {
+            name: name.to_owned(),
+            fraction: 1.0,
+            note: None,
+        }
+    }
+
+    pub fn with_note(self, note: &str) -> DayOff {
+        DayOff {
+            note: Some(note.to_owned()).filter(|note| !note.is_empty()),
+            ..self
+        }
+    }
+}
+
+pub type Holidays = BTreeMap<NaiveDate, DayOff>;
+
+/// Whether a holiday is counted as day off
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum HolidayRule {
+    Include,
+    Exclude,
+}
+
+/// Decides which of the holidays with restrictions (see [DayOff::note]) apply to
+/// the municipality of the user. Holidays without restrictions are always counted,
+/// unless excluded by name.
+#[derive(Debug, Clone)]
+pub struct HolidayRules {
+    pub regional: HolidayRule,
+    pub by_name: BTreeMap<String, HolidayRule>,
+}
+
+impl Default for HolidayRules {
+    fn default() -> Self {
+        HolidayRules {
+            regional: HolidayRule::Include,
+            by_name: BTreeMap::new(),
+        }
+    }
+}
+
+impl HolidayRules {
+    pub fn applies(&self, holiday: &DayOff) -> bool {
+        let rule = match (self.by_name.get(&holiday.name), &holiday.note) {
+            (Some(rule), _) => *rule,
+            (None, Some(_)) => self.regional,
+            (None, None) => HolidayRule::Include,
+        };
+        rule == HolidayRule::Include
+    }
+}
+
+/// Source of public holidays for one region
+pub trait HolidayProvider {
+    fn holidays(&self, year: i32) -> Result<Holidays, anyhow::Error>;
+}
+
+/// Region the public holidays are taken for
+#[derive(Debug, Clone)]
+pub enum Region {
+    Germany(Bundesland),
+    Austria,
+    Switzerland(Kanton),
+    Custom(PathBuf),
+}
+
+impl Region {
+    pub fn from_names(
+        land: Option<&str>,
+        bundesland: Option<&str>,
+        kanton: Option<&str>,
+        file: Option<&Path>,
+    ) -> Result<Region, anyhow::Error> {
+        if let Some(file) = file {
+            return Ok(Region::Custom(file.to_owned()));
+        }
+
+        match land.map(normalize_name).as_deref() {
+            None | Some("de") | Some("deutschland") => Ok(Region::Germany(
+                bundesland
+                    .map(|v| v.parse())
+                    .unwrap_or(Ok(Bundesland::NW))?,
+            )),
+            Some("at") | Some("oesterreich") => Ok(Region::Austria),
+            Some("ch") | Some("schweiz") => Ok(Region::Switzerland(
+                kanton
+                    .ok_or_else(|| anyhow!("Please configure a Kanton for swiss holidays"))?
+                    .parse()?,
+            )),
+            Some(_) => Err(anyhow!(
+                "Unknown land '{}', expected DE, AT or CH",
+                land.unwrap_or_default()
+            )),
+        }
+    }
+
+    pub fn provider(&self) -> Result<Box<dyn HolidayProvider>, anyhow::Error> {
+        Ok(match self {
+            Region::Germany(state) => Box::new(*state),
+            Region::Austria => Box::new(crate::feiertage_at::Oesterreich),
+            Region::Switzerland(kanton) => Box::new(*kanton),
+            Region::Custom(file) => Box::new(StaticList::load(file)?),
+        })
+    }
+}
+
+impl std::fmt::Display for Region {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Region::Germany(state) => write!(f, "{}", state),
+            Region::Austria => write!(f, "Österreich"),
+            Region::Switzerland(kanton) => write!(f, "Schweiz, {}", kanton),
+            Region::Custom(file) => write!(f, "{}", file.display()),
+        }
+    }
+}
+
+/// Lower case, without umlauts and with dashes instead of blanks. Used to compare
+/// names of states given by the user.
+pub fn normalize_name(s: &str) -> String {
+    s.trim()
+        .to_lowercase()
+        .replace('ä', "ae")
+        .replace('ö', "oe")
+        .replace('ü', "ue")
+        .replace('ß', "ss")
+        .replace(' ', "-")
+}
+
+/// A day which is only partially off, like Heiligabend. Configured as `"12-24": 0.5`
+/// for every year or as `"2018-12-24": 0.5` for a single day.
+#[derive(Debug, Clone)]
+pub struct PartialHoliday {
+    year: Option<i32>,
+    month: u32,
+    day: u32,
+    pub name: String,
+    /// Share of the day which is off, `0.5` for a half day
+    pub fraction: f32,
+}
+
+impl PartialHoliday {
+    pub fn parse(date: &str, name: &str, fraction: f32) -> Result<PartialHoliday, anyhow::Error> {
+        if !(0.0..=1.0).contains(&fraction) {
+            return Err(anyhow!(
+                "Fraction of partial holiday {date} must be between 0 and 1"
+            ));
+        }
+
+        let parts = date
+            .split('-')
+            .map(|v| v.parse::<i32>())
+            .collect::<Result<Vec<_>, _>>()
+            .with_context(|| format!("Invalid partial holiday '{date}', expected MM-DD"))?;
+
+        let (year, month, day) = match parts[..] {
+            [month, day] => (None, month, day),
+            [year, month, day] => (Some(year), month, day),
+            _ => return Err(anyhow!("Invalid partial holiday '{date}', expected MM-DD")),
+        };
+
+        let result = PartialHoliday {
+            year,
+            month: month as u32,
+            day: day as u32,
+            name: name.to_owned(),
+            fraction,
+        };
+        // 02-29 is fine, as long as there is a leap year
+        if result.date_in(year.unwrap_or(2000)).is_none() {
+            return Err(anyhow!("Invalid partial holiday '{date}'"));
+        }
+        Ok(result)
+    }
+
+    pub fn date_in(&self, year: i32) -> Option<NaiveDate> {
+        match self.year {
+            Some(y) if y != year => None,
+            _ => NaiveDate::from_ymd_opt(year, self.month, self.day),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+enum StaticDates {
+    Single(NaiveDate),
+    Multiple(Vec<NaiveDate>),
+}
+
+/// Holidays read from a file, which maps names to one or more dates, e.g.
+/// `{"Sechseläuten": ["2023-04-17", "2024-04-15"], "Knabenschiessen": "2024-09-09"}`
+pub struct StaticList {
+    inner: BTreeMap<String, StaticDates>,
+}
+
+impl StaticList {
+    pub fn load(file: &Path) -> Result<StaticList, anyhow::Error> {
+        let reader = std::fs::File::open(file)
+            .with_context(|| format!("Could not open holiday file {file:#?}"))?;
+        let inner = serde_json::from_reader(reader)
+            .with_context(|| format!("Could not parse holiday file {file:#?}"))?;
+        Ok(StaticList { inner })
+    }
+}
+
+impl HolidayProvider for StaticList {
+    fn holidays(&self, year: i32) -> Result<Holidays, anyhow::Error> {
+        Ok(self
+            .inner
+            .iter()
+            .flat_map(|(name, dates)| {
+                let dates = match dates {
+                    StaticDates::Single(day) => vec![*day],
+                    StaticDates::Multiple(days) => days.clone(),
+                };
+                dates
+                    .into_iter()
+                    .map(move |day| (day, DayOff::whole_day(name)))
+            })
+            .filter(|(day, _)| day.year() == year)
+            .collect())
+    }
+}
+
+#[test]
+fn test_static_list() {
+    let list = StaticList {
+        inner: serde_json::from_str(
+            r#"{
+        "Sechseläuten": ["2023-04-17", "2024-04-15"],
+        "Knabenschiessen": "2024-09-09"
+    }"#,
+        )
+        .unwrap(),
+    };
+
+    let holidays = list.holidays(2024).unwrap();
+    assert_eq!(holidays.len(), 2);
+    assert_eq!(
+        holidays
+            .get(&NaiveDate::from_ymd(2024, 4, 15))
+            .unwrap()
+            .name,
+        "Sechseläuten"
+    );
+}
+
+#[test]
+fn test_region_from_names() {
+    assert!(matches!(
+        Region::from_names(None, None, None, None).unwrap(),
+        Region::Germany(Bundesland::NW)
+    ));
+    assert!(matches!(
+        Region::from_names(Some("Österreich"), None, None, None).unwrap(),
+        Region::Austria
+    ));
+    assert!(matches!(
+        Region::from_names(Some("CH"), None, Some("Zürich"), None).unwrap(),
+        Region::Switzerland(Kanton::ZH)
+    ));
+    assert!(Region::from_names(Some("CH"), None, None, None).is_err());
+}
+
+#[test]
+fn test_partial_holiday() {
+    let heiligabend = PartialHoliday::parse("12-24", "Heiligabend", 0.5).unwrap();
+    assert_eq!(
+        heiligabend.date_in(2018),
+        Some(NaiveDate::from_ymd(2018, 12, 24))
+    );
+
+    let once = PartialHoliday::parse("2018-12-31", "Silvester", 0.5).unwrap();
+    assert_eq!(once.date_in(2018), Some(NaiveDate::from_ymd(2018, 12, 31)));
+    assert_eq!(once.date_in(2019), None);
+
+    assert!(PartialHoliday::parse("24.12.", "Heiligabend", 0.5).is_err());
+    assert!(PartialHoliday::parse("12-24", "Heiligabend", 1.5).is_err());
+}
+
+#[test]
+fn test_holiday_rules() {
+    let fronleichnam = DayOff::whole_day("Fronleichnam").with_note("Nur in einigen Gemeinden");
+    let neujahr = DayOff::whole_day("Neujahrstag");
+
+    let mut rules = HolidayRules::default();
+    assert!(rules.applies(&fronleichnam));
+    assert!(rules.applies(&neujahr));
+
+    rules.regional = HolidayRule::Exclude;
+    assert!(!rules.applies(&fronleichnam));
+    assert!(rules.applies(&neujahr));
+
+    rules
+        .by_name
+        .insert("Fronleichnam".to_owned(), HolidayRule::Include);
+    assert!(rules.applies(&fronleichnam));
+}
@@ -0,0 +1,304 @@
+use crate::holidays::{DayOff, HolidayProvider, Holidays};
+use anyhow::{anyhow, Context};
+use chrono::{Datelike, NaiveDate};
+use std::path::Path;
+
+/// Non-working days from an iCalendar file, e.g. company closure days published by HR.
+///
+/// Only the parts needed for that are understood: `VEVENT`s with all-day or timed
+/// `DTSTART`/`DTEND`, spanning one or more days, optionally repeated with
+/// `RRULE:FREQ=YEARLY` (`INTERVAL`, `COUNT` and `UNTIL` are honoured).
+#[derive(Debug, Default)]
+pub struct Calendar {
+    events: Vec<Event>,
+}
+
+#[derive(Debug, Clone)]
+struct Event {
+    summary: String,
+    first_day: NaiveDate,
+    last_day: NaiveDate,
+    yearly: Option<YearlyRule>,
+}
+
+#[derive(Debug, Clone)]
+struct YearlyRule {
+    interval: i32,
+    count: Option<i32>,
+    until: Option<NaiveDate>,
+}
+
+impl Calendar {
+    pub fn load(file: &Path) -> Result<Calendar, anyhow::Error> {
+        let content = std::fs::read_to_string(file)
+            .with_context(|| format!("Could not read calendar {file:#?}"))?;
+        content
+            .parse()
+            .with_context(|| format!("Could not parse calendar {file:#?}"))
+    }
+}
+
+impl std::str::FromStr for Calendar {
+    type Err = anyhow::Error;
+
+    fn from_str(content: &str) -> Result<Self, Self::Err> {
+        let mut calendar = Calendar::default();
+        let mut current: Option<Vec<(String, String)>> = None;
+
+        for line in unfold(content) {
+            let (name, value) = match line.split_once(':') {
+                Some((name, value)) => (name, value),
+                None => continue,
+            };
+            // parameters like in `DTSTART;VALUE=DATE` are not needed
+            let name = name.split(';').next().unwrap_or(name);
+
+            match (name.to_uppercase().as_str(), value.trim()) {
+                ("BEGIN", "VEVENT") => current = Some(Vec::new()),
+                ("END", "VEVENT") => {
+                    if let Some(properties) = current.take() {
+                        calendar.events.push(Event::from_properties(&properties)?);
+                    }
+                }
+                (name, _) => {
+                    if let Some(properties) = current.as_mut() {
+                        properties.push((name.to_owned(), value.to_owned()));
+                    }
+                }
+            }
+        }
+
+        Ok(calendar)
+    }
+}
+
+/// Joins continuation lines, which start with a blank or tab
+fn unfold(content: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in content.lines() {
+        let line = line.trim_end_matches('\r');
+        match (
+            line.strip_prefix(' ').or_else(|| line.strip_prefix('\t')),
+            lines.last_mut(),
+        ) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(line.to_owned()),
+        }
+    }
+    lines
+}
+
+/// Date part of `20181224` or `20181224T080000Z`
+fn parse_date(value: &str) -> Result<(NaiveDate, bool), anyhow::Error> {
+    let value = value.trim();
+    let (date, time) = value.split_once('T').unwrap_or((value, ""));
+    let date = NaiveDate::parse_from_str(date, "%Y%m%d")
+        .with_context(|| format!("Invalid date '{value}'"))?;
+    let at_midnight = time.trim_end_matches('Z').trim_matches('0').is_empty();
+    Ok((date, at_midnight))
+}
+
+fn unescape(value: &str) -> String {
+    value
+        .replace("\\n", " ")
+        .replace("\\N", " ")
+        .replace("\\,", ",")
+        .replace("\\;", ";")
+        .replace("\\\\", "\\")
+}
+
+impl Event {
+    fn from_properties(properties: &[(String, String)]) -> Result<Event, anyhow::Error> {
+        let property = |wanted: &str| {
+            properties
+                .iter()
+                .find(|(name, _)| name == wanted)
+                .map(|(_, value)| value.as_str())
+        };
+
+        let summary = property("SUMMARY").map(unescape).unwrap_or_default();
+        let (first_day, _) = parse_date(
+            property("DTSTART").ok_or_else(|| anyhow!("Event '{summary}' without DTSTART"))?,
+        )?;
+
+        // DTEND is exclusive, but a timed event ending during the day covers that day too
+        let last_day = match property("DTEND").map(parse_date).transpose()? {
+            Some((end, true)) if end > first_day => end.pred(),
+            Some((end, _)) => end,
+            None => first_day,
+        };
+
+        let yearly = property("RRULE")
+            .map(|rule| YearlyRule::parse(rule, &summary))
+            .transpose()?
+            .flatten();
+
+        Ok(Event {
+            summary,
+            first_day,
+            last_day,
+            yearly,
+        })
+    }
+
+    /// Days covered by the occurence of this event starting in `year`
+    fn days_in(&self, year: i32) -> Vec<NaiveDate> {
+        let offset = year - self.first_day.year();
+
+        let applies = match &self.yearly {
+            None => offset == 0,
+            Some(rule) => {
+                offset >= 0
+                    && offset % rule.interval == 0
+                    && !matches!(rule.count, Some(count) if offset / rule.interval >= count)
+            }
+        };
+        let first_day = match (applies, self.first_day.with_year(year)) {
+            (true, Some(day)) => day,
+            _ => return Vec::new(),
+        };
+        if let Some(until) = self.yearly.as_ref().and_then(|rule| rule.until) {
+            if first_day > until {
+                return Vec::new();
+            }
+        }
+
+        let mut days = Vec::new();
+        let mut day = first_day;
+        while day <= first_day + (self.last_day - self.first_day) {
+            days.push(day);
+            day = day.succ();
+        }
+        days
+    }
+}
+
+impl YearlyRule {
+    /// Only yearly rules are supported, others are reported and ignored.
+    fn parse(rule: &str, summary: &str) -> Result<Option<YearlyRule>, anyhow::Error> {
+        let mut result = YearlyRule {
+            interval: 1,
+            count: None,
+            until: None,
+        };
+        let mut yearly = false;
+
+        for part in rule.split(';') {
+            match part.split_once('=') {
+                Some(("FREQ", freq)) => yearly = freq == "YEARLY",
+                Some(("INTERVAL", interval)) => {
+                    result.interval = interval.parse().with_context(|| {
+                        format!("Invalid INTERVAL in RRULE '{rule}' of '{summary}'")
+                    })?
+                }
+                Some(("COUNT", count)) => {
+                    result.count = Some(count.parse().with_context(|| {
+                        format!("Invalid COUNT in RRULE '{rule}' of '{summary}'")
+                    })?)
+                }
+                Some(("UNTIL", until)) => {
+                    result.until = Some(
+                        parse_date(until)
+                            .with_context(|| {
+                                format!("Invalid UNTIL in RRULE '{rule}' of '{summary}'")
+                            })?
+                            .0,
+                    )
+                }
+                _ => {}
+            }
+        }
+        if result.interval < 1 {
+            return Err(anyhow!(
+                "INTERVAL in RRULE '{rule}' of '{summary}' needs to be at least 1"
+            ));
+        }
+
+        if yearly {
+            Ok(Some(result))
+        } else {
+            eprintln!("Warning: ignoring unsupported RRULE '{rule}' of '{summary}'");
+            Ok(None)
+        }
+    }
+}
+
+impl HolidayProvider for Calendar {
+    fn holidays(&self, year: i32) -> Result<Holidays, anyhow::Error> {
+        Ok(self
+            .events
+            .iter()
+            .flat_map(|event| {
+                // an event starting in the previous year might reach into this one
+                let mut days = event.days_in(year - 1);
+                days.extend(event.days_in(year));
+                days.into_iter()
+                    .map(move |day| (day, DayOff::whole_day(&event.summary)))
+            })
+            .filter(|(day, _)| day.year() == year)
+            .collect())
+    }
+}
+
+#[test]
+fn test_ical() {
+    let calendar: Calendar = "BEGIN:VCALENDAR\r
+VERSION:2.0\r
+BEGIN:VEVENT\r
+UID:1@hr\r
+DTSTART;VALUE=DATE:20181227\r
+DTEND;VALUE=DATE:20181229\r
+SUMMARY:Betriebsruhe zwischen \r
+ den Jahren\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:2@hr\r
+DTSTART;VALUE=DATE:20171224\r
+RRULE:FREQ=YEARLY;COUNT=3\r
+SUMMARY:Heiligabend\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+DTSTART:20180615T090000Z\r
+DTEND:20180615T170000Z\r
+SUMMARY:Betriebsausflug\r
+END:VEVENT\r
+END:VCALENDAR\r
+"
+    .parse()
+    .unwrap();
+
+    let holidays = calendar.holidays(2018).unwrap();
+    assert_eq!(
+        holidays
+            .into_iter()
+            .map(|(day, holiday)| (day, holiday.name))
+            .collect::<Vec<_>>(),
+        vec![
+            (
+                NaiveDate::from_ymd(2018, 6, 15),
+                "Betriebsausflug".to_owned()
+            ),
+            (NaiveDate::from_ymd(2018, 12, 24), "Heiligabend".to_owned()),
+            (
+                NaiveDate::from_ymd(2018, 12, 27),
+                "Betriebsruhe zwischen den Jahren".to_owned()
+            ),
+            (
+                NaiveDate::from_ymd(2018, 12, 28),
+                "Betriebsruhe zwischen den Jahren".to_owned()
+            ),
+        ]
+    );
+
+    assert!(calendar.holidays(2019).unwrap().len() == 1);
+    assert!(calendar.holidays(2020).unwrap().is_empty());
+
+    let zero_interval = "BEGIN:VEVENT\r
+DTSTART;VALUE=DATE:20171224\r
+RRULE:FREQ=YEARLY;INTERVAL=0\r
+SUMMARY:Heiligabend\r
+END:VEVENT\r
+"
+    .parse::<Calendar>();
+    assert!(format!("{:#}", zero_interval.unwrap_err()).contains("Heiligabend"));
+}
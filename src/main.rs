@@ -5,200 +5,802 @@ extern crate serde_derive;
 extern crate prettytable;
 
 mod absence;
+mod csv;
 mod date_helper;
 mod feiertage;
+mod feiertage_at;
+mod feiertage_ch;
+mod holidays;
+mod ical;
 mod program_config;
 mod redmine;
+mod report;
+mod schedule;
+mod vacation;
 
+use crate::absence::{AbsenceKind, Absences};
 use crate::date_helper::*;
-use crate::program_config::Settings;
+use crate::holidays::{DayOff, HolidayProvider, Holidays, Region};
+use crate::program_config::{AbsenceCommand, Command, LazyClient, Settings};
+use crate::redmine::TimeEntry;
+use crate::report::{fmt_cell, fmt_days, OutputFormat, Period, Report};
 use anyhow::Context;
 use chrono::{Datelike, Duration, NaiveDate, Weekday};
 use prettytable::{format, Cell, Row, Table};
-use reqwest::Client;
-use std::{collections::BTreeSet, iter::FromIterator};
+use std::collections::BTreeMap;
 
 #[tokio::main]
 async fn main() -> Result<(), anyhow::Error> {
     let s = program_config::get_settings()?;
+    // editing absence.json works without network settings
+    if let Command::Absence(command) = &s.command {
+        return manage_absences(&s, command).await;
+    }
 
-    let planned_absence =
-        BTreeSet::from_iter(absence::get_days_of_absence(s.from, s.to)?.into_iter());
-    let vacation_days = get_vacation_days_for(&s).await?;
+    let http_client = LazyClient::new(&s.network);
+    if let Command::Entries = s.command {
+        return print_entries(&s, &http_client).await;
+    }
 
-    let mut table = Table::new();
-    table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
-    table.set_titles(row![
-        "Monat",
-        "Arbeitstage",
-        "davon Abwesend",
-        "Sollstunden",
-        "Redmine-Stunden",
-        "Differenz"
-    ]);
+    let vacation_days = get_vacation_days_for(&s, &http_client).await?;
 
-    let table_data =
-        query_redmine(&s, planned_absence, vacation_days, reqwest::Client::new()).await?;
+    match s.command {
+        Command::Report => print_report(&s, vacation_days, &http_client).await?,
+        Command::Holidays => print_holidays(&s, &vacation_days),
+        Command::Vacation => print_vacation(&s, &vacation_days)?,
+        Command::Absence(_) | Command::Entries => unreachable!("handled before"),
+    }
 
-    for (start_date, end_date, data) in table_data.iter() {
-        let last_day_included = end_date.pred();
+    Ok(())
+}
 
-        if last_day_included - *start_date == Duration::zero() {
-            continue;
+async fn print_report(
+    s: &Settings,
+    vacation_days: Holidays,
+    http_client: &LazyClient,
+) -> Result<(), anyhow::Error> {
+    let report = build_report(s, &vacation_days, http_client).await?;
+    match s.format {
+        OutputFormat::Table => {}
+        OutputFormat::Json => {
+            println!("{}", report.to_json()?);
+            return Ok(());
         }
+        OutputFormat::Csv => {
+            print!("{}", report.to_csv(&s.csv));
+            return Ok(());
+        }
+        OutputFormat::Markdown => {
+            print!("{}", report.to_markdown(s.show_holidays));
+            return Ok(());
+        }
+        OutputFormat::Html => {
+            print!("{}", report.to_html(s.show_holidays));
+            return Ok(());
+        }
+    }
 
-        table.add_row(make_row(
-            Cell::new(&if (*end_date - *start_date).num_days() > 7 {
-                format!("{}/{:02}", start_date.year(), start_date.month())
-            } else if start_date.weekday() == Weekday::Mon {
-                format!(
-                    "{} KW{:02}",
-                    start_date.year(),
-                    start_date.iso_week().week()
-                )
-            } else if start_date.month() == last_day_included.month() {
-                format!(
-                    "{}/{:02}/{:02} - {:02}",
-                    start_date.year(),
-                    start_date.month(),
-                    start_date.day(),
-                    last_day_included.day()
-                )
-            } else {
-                format!(
-                    "{}/{:02}/{:02} - {:02}/{:02}",
-                    start_date.year(),
-                    start_date.month(),
-                    start_date.day(),
-                    last_day_included.month(),
-                    last_day_included.day()
-                )
+    println!("Feiertage: {}", s.region);
+    report.to_table(s.show_holidays).printstd();
+
+    if let Some(balance) = &report.balance {
+        println!(
+            "Saldo: {:.2} Stunden am {} zuzüglich Differenz seitdem",
+            balance.opening_hours,
+            balance.opening_date.format("%d.%m.%Y")
+        );
+    }
+
+    let partial_days = vacation_days
+        .range(s.from..=s.to)
+        .filter(|(day, day_off)| {
+            day_off.fraction > 0.0 && day_off.fraction < 1.0 && !is_weekend(**day)
+        })
+        .map(|(day, day_off)| {
+            format!(
+                "{} {} ({} frei)",
+                day.format("%d.%m.%Y"),
+                day_off.name,
+                day_off.fraction
+            )
+        })
+        .collect::<Vec<_>>();
+    if !partial_days.is_empty() {
+        println!("Teilweise freie Tage: {}", partial_days.join(", "));
+    }
+
+    for (day, day_off) in vacation_days.range(s.from..=s.to) {
+        if let Some(note) = &day_off.note {
+            println!(
+                "Hinweis zu {} am {} ({}): {}",
+                day_off.name,
+                day.format("%d.%m.%Y"),
+                if day_off.fraction > 0.0 {
+                    "gezählt"
+                } else {
+                    "nicht gezählt"
+                },
+                note
+            );
+        }
+    }
+
+    if (s.from..=s.to).contains(&today()) {
+        print_forecast(s, http_client).await?;
+    }
+
+    Ok(())
+}
+
+async fn build_report(
+    s: &Settings,
+    vacation_days: &Holidays,
+    http_client: &LazyClient,
+) -> Result<Report, anyhow::Error> {
+    let absences = absence::AbsenceConfig::load()?;
+    let day_off = |d: &NaiveDate| is_day_off(s, vacation_days, d);
+    let hours = |d: &NaiveDate| s.working_time.hours(d);
+    for warning in absences.check(s.from, s.to, today(), &hours, &day_off) {
+        eprintln!("Warning: {warning}");
+    }
+    let planned_absence = absences.days_of_absence(s.from, s.to, &hours);
+
+    let entries = fetch_entries(s, s.from, s.to.succ(), http_client).await?;
+    let row = |from, to| compute_table_row(vacation_days, &planned_absence, s, &entries, from, to);
+    let table_data = date_helper::periods(s.from, s.to, s.grouping)
+        .into_iter()
+        .map(|(start_date, end_date)| row(start_date, end_date))
+        .collect::<Vec<_>>();
+
+    // the latest balance before the report, later ones reset the cumulated difference
+    let start = match s
+        .overtime_balances
+        .iter()
+        .rev()
+        .find(|balance| balance.date < s.from)
+    {
+        Some(balance) => {
+            balance.hours + difference_between(s, balance.date, s.from, http_client).await?
+        }
+        None => 0.0,
+    };
+    let cumulated = report::cumulate(
+        &table_data
+            .iter()
+            .map(|(start_date, end_date, data)| {
+                (*start_date, *end_date, data.redmine_hours - data.work_hours)
             })
-            .style_spec("i"),
-            &data,
+            .collect::<Vec<_>>(),
+        start,
+        &s.overtime_balances,
+        &|from, to| {
+            let (_, _, data) = row(from, to);
+            data.redmine_hours - data.work_hours
+        },
+    );
+    let cumulative = cumulated.last().copied().unwrap_or(start);
+
+    let mut periods = Vec::new();
+    for ((start_date, end_date, data), cumulative) in table_data.iter().zip(cumulated) {
+        let holidays = vacation_days
+            .range(*start_date..*end_date)
+            .filter(|(day, day_off)| day_off.fraction > 0.0 && !is_weekend(**day))
+            .map(|(_, day_off)| day_off.name.clone())
+            .collect();
+        periods.push(make_period(
+            caption(*start_date, *end_date, s.grouping),
+            *start_date,
+            *end_date,
+            data,
+            cumulative,
+            holidays,
         ));
     }
 
-    if table_data.len() > 1 {
-        let sum = table_data
+    let sum = table_data
+        .iter()
+        .fold(RowData::default(), |accum, (_, _, data)| {
+            accum + data.clone()
+        });
+    let total = make_period(
+        "Gesamt".to_owned(),
+        s.from,
+        s.to.succ(),
+        &sum,
+        cumulative,
+        periods
             .iter()
-            .fold(RowData::default(), |accum, (_, _, data)| {
-                accum + data.clone()
-            });
+            .flat_map(|period| period.holidays.iter().cloned())
+            .collect(),
+    );
+
+    let balance = s
+        .overtime_balances
+        .iter()
+        .rev()
+        .find(|balance| balance.date <= s.to)
+        .map(|balance| report::Balance {
+            opening_date: balance.date,
+            opening_hours: balance.hours,
+            hours: cumulative,
+        });
+
+    Ok(Report {
+        from: s.from,
+        to: s.to,
+        periods,
+        total,
+        balance,
+        grouping: s.grouping,
+    })
+}
 
-        table.add_empty_row();
-        table.add_row(make_row(Cell::new("Gesamt").style_spec("b"), &sum));
+/// Caption of the period `start_date..end_date`
+fn caption(start_date: NaiveDate, end_date: NaiveDate, grouping: Grouping) -> String {
+    let last_day_included = end_date.pred();
+    match grouping {
+        Grouping::Auto => {}
+        Grouping::Day => return start_date.format("%Y/%m/%d").to_string(),
+        Grouping::Week => {
+            let week = start_date.iso_week();
+            return format!("{} KW{:02}", week.year(), week.week());
+        }
+        Grouping::Month => return format!("{}/{:02}", start_date.year(), start_date.month()),
+        Grouping::Quarter => {
+            return format!("{} Q{}", start_date.year(), start_date.month0() / 3 + 1)
+        }
+        Grouping::Year => return start_date.year().to_string(),
+    }
+    if (end_date - start_date).num_days() > 7 {
+        format!("{}/{:02}", start_date.year(), start_date.month())
+    } else if start_date.weekday() == Weekday::Mon {
+        format!(
+            "{} KW{:02}",
+            start_date.year(),
+            start_date.iso_week().week()
+        )
+    } else if start_date.month() == last_day_included.month() {
+        format!(
+            "{}/{:02}/{:02} - {:02}",
+            start_date.year(),
+            start_date.month(),
+            start_date.day(),
+            last_day_included.day()
+        )
+    } else {
+        format!(
+            "{}/{:02}/{:02} - {:02}/{:02}",
+            start_date.year(),
+            start_date.month(),
+            start_date.day(),
+            last_day_included.month(),
+            last_day_included.day()
+        )
+    }
+}
+
+/// `cumulative` is the difference up to the end of the period
+fn make_period(
+    caption: String,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+    data: &RowData,
+    cumulative: f32,
+    holidays: Vec<String>,
+) -> Period {
+    Period {
+        caption,
+        start: start_date,
+        end: end_date.pred(),
+        workdays: data.workdays,
+        absence_days: data.days_of_absence,
+        absence_by_kind: data.absence_by_kind.clone(),
+        target_hours: data.work_hours,
+        redmine_hours: data.redmine_hours,
+        difference: data.redmine_hours - data.work_hours,
+        cumulative,
+        holidays,
+    }
+}
+
+/// All time entries in the range as csv, including the ones booked as absence
+async fn print_entries(s: &Settings, client: &LazyClient) -> Result<(), anyhow::Error> {
+    let mut entries = fetch_entries(s, s.from, s.to.succ(), client).await?;
+    entries.sort_by_key(|entry| (entry.spent_on, entry.id));
+
+    let timestamp = |time: &chrono::DateTime<chrono::Utc>| {
+        time.with_timezone(&chrono::Local)
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string()
+    };
+    print!(
+        "{}",
+        s.csv.line(&[
+            "ID",
+            "Datum",
+            "Projekt",
+            "Ticket",
+            "Aktivität",
+            "Stunden",
+            "Kommentar",
+            "Erstellt",
+            "Geändert"
+        ])
+    );
+    for entry in entries {
+        print!(
+            "{}",
+            s.csv.line(&[
+                entry.id.to_string(),
+                entry.spent_on.to_string(),
+                entry.project.name,
+                entry
+                    .issue
+                    .map(|issue| issue.id.to_string())
+                    .unwrap_or_default(),
+                entry.activity.name,
+                s.csv.number(entry.hours),
+                entry.comments,
+                timestamp(&entry.created_on),
+                timestamp(&entry.updated_on),
+            ])
+        );
+    }
+    Ok(())
+}
+
+fn print_holidays(s: &Settings, vacation_days: &Holidays) {
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+    table.set_titles(row!["Datum", "Tag", "Feiertag", "Frei", "Hinweis"]);
+
+    for (day, day_off) in vacation_days.range(s.from..=s.to) {
+        let weekday = Cell::new(weekday_name(day.weekday()));
+        table.add_row(Row::new(vec![
+            Cell::new(&day.format("%d.%m.%Y").to_string()),
+            if is_weekend(*day) {
+                weekday.style_spec("i")
+            } else {
+                weekday
+            },
+            Cell::new(&day_off.name),
+            fmt_days(day_off.fraction),
+            Cell::new(day_off.note.as_deref().unwrap_or_default()),
+        ]));
     }
 
+    println!("Feiertage: {}", s.region);
     table.printstd();
+}
 
+fn print_vacation(s: &Settings, vacation_days: &Holidays) -> Result<(), anyhow::Error> {
+    let entitlement = s.vacation.as_ref().ok_or_else(|| {
+        anyhow::anyhow!("No vacation entitlement configured, add \"vacation\" to config.json")
+    })?;
+
+    let years = years_in_range(s.from, s.to);
+    let planned_absence = absence::get_days_of_absence(
+        NaiveDate::from_ymd(years.start, 1, 1),
+        NaiveDate::from_ymd(years.end - 1, 12, 31),
+        &|d| s.working_time.hours(d),
+    )?;
+    let vacation =
+        |d: &NaiveDate| absent_share(&planned_absence, vacation_days, d, AbsenceKind::Vacation);
+
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+    table.set_titles(row![
+        "Jahr",
+        "Anspruch",
+        "Übertrag",
+        "verfallen",
+        "genommen",
+        "geplant",
+        "Rest"
+    ]);
+    for year in entitlement.balance(years, today(), &s.working_time, &vacation)? {
+        table.add_row(Row::new(vec![
+            Cell::new(&year.year.to_string()).style_spec("i"),
+            fmt_days(year.entitlement),
+            fmt_days(year.carry_over),
+            fmt_days(year.expired),
+            fmt_days(year.taken),
+            fmt_days(year.planned),
+            fmt_cell(year.remaining),
+        ]));
+    }
+    table.printstd();
     Ok(())
 }
 
-async fn query_redmine(
-    s: &Settings,
-    planned_absence: BTreeSet<NaiveDate>,
-    vacation_days: BTreeSet<NaiveDate>,
-    http_client: Client,
-) -> anyhow::Result<Vec<(NaiveDate, NaiveDate, RowData)>> {
-    let mut tasks: Vec<tokio::task::JoinHandle<_>> = Vec::new();
-    for (start_date, end_date) in get_date_ranges_to_query(&s) {
-        let vacation_days = vacation_days.clone();
-        let planned_absence = planned_absence.clone();
-        let client = http_client.clone();
-        let settings = s.clone();
-        tasks.push(tokio::spawn(async move {
-            compute_table_row(
-                vacation_days,
-                planned_absence,
-                settings,
-                client,
-                start_date,
-                end_date,
-            )
-            .await
-        }));
+async fn manage_absences(s: &Settings, command: &AbsenceCommand) -> Result<(), anyhow::Error> {
+    if let AbsenceCommand::Check = command {
+        return check_absences(s, &LazyClient::new(&s.network)).await;
     }
+    let mut file = absence::AbsenceFile::open()?;
 
-    let mut table_data = Vec::new();
-    for task in tasks {
-        let res = task.await??;
-        table_data.push(res);
+    match command {
+        AbsenceCommand::Add {
+            name,
+            kind,
+            first_day,
+            last_day,
+        } => {
+            file.add(name, *kind, *first_day, *last_day)?;
+            file.save()?;
+            println!("Abwesenheit '{name}' eingetragen");
+        }
+        AbsenceCommand::Remove { name } => {
+            file.remove(name)?;
+            file.save()?;
+            println!("Abwesenheit '{name}' entfernt");
+        }
+        AbsenceCommand::List { year } => {
+            let mut table = Table::new();
+            table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+            table.set_titles(row!["Name", "Art", "Von", "Bis", "Umfang"]);
+            for absence in file.list(*year)? {
+                table.add_row(row![
+                    absence.name,
+                    absence.kind.name(),
+                    absence.first_day.format("%d.%m.%Y"),
+                    absence.last_day.format("%d.%m.%Y"),
+                    absence.extent
+                ]);
+            }
+            table.printstd();
+        }
+        AbsenceCommand::Check => unreachable!("handled before"),
     }
-    Ok(table_data)
+    Ok(())
 }
 
-/// vec of ranges, 2nd item won't be included in query
-fn get_date_ranges_to_query(s: &Settings) -> Vec<(NaiveDate, NaiveDate)> {
-    let mut cur = s.from;
-    let mut dates = Vec::new();
-
-    loop {
-        if cur < s.to {
-            cur = if next_month(cur) < s.to {
-                dates.push((cur, next_month(cur)));
-                next_month(cur)
-            } else if next_week_monday(cur) < s.to {
-                dates.push((cur, next_week_monday(cur)));
-                next_week_monday(cur)
-            } else {
-                dates.push((cur, s.to.succ()));
-                s.to.succ()
-            };
-        } else {
-            break;
+/// Checks all entries, holidays are needed for all years they cover
+async fn check_absences(s: &Settings, client: &LazyClient) -> Result<(), anyhow::Error> {
+    let absences = absence::AbsenceConfig::load()?;
+    let (from, to) = match absences.span() {
+        Some(span) => span,
+        None => return Ok(()),
+    };
+    let settings = Settings {
+        from,
+        to,
+        check_holidays_online: false,
+        ..s.clone()
+    };
+    let vacation_days = get_vacation_days_for(&settings, client).await?;
+    let day_off = |d: &NaiveDate| is_day_off(s, &vacation_days, d);
+
+    let warnings = absences.check(from, to, today(), &|d| s.working_time.hours(d), &day_off);
+    for warning in &warnings {
+        println!("{warning}");
+    }
+    if warnings.is_empty() {
+        println!("Keine Auffälligkeiten in absence.json");
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "Found {} problem(s) in absence.json",
+            warnings.len()
+        ))
+    }
+}
+
+/// Row of the report for `start_date..end_date`, `entries` may cover a larger range
+fn compute_table_row(
+    vacation_days: &Holidays,
+    planned_absence: &Absences,
+    s: &Settings,
+    entries: &[TimeEntry],
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> (NaiveDate, NaiveDate, RowData) {
+    let (redmine, booked_absence) = row_data_from_entries(entries, start_date, end_date, s);
+    // absences in absence.json and booked in redmine are likely the same
+    let mut planned_absence = planned_absence.clone();
+    for (day, kinds) in booked_absence {
+        let planned = planned_absence.entry(day).or_default();
+        for (kind, share) in kinds {
+            let planned_share = planned.entry(kind).or_insert(0.0);
+            *planned_share = planned_share.max(share);
         }
     }
-    dates
+
+    (
+        start_date,
+        end_date,
+        planned_row_data(vacation_days, &planned_absence, s, start_date, end_date) + redmine,
+    )
 }
 
-async fn compute_table_row(
-    vacation_days: BTreeSet<NaiveDate>,
-    planned_absence: BTreeSet<NaiveDate>,
-    s: Settings,
-    client: Client,
+/// Working days, absences and target hours in `start_date..end_date`, without redmine hours
+fn planned_row_data(
+    vacation_days: &Holidays,
+    planned_absence: &Absences,
+    s: &Settings,
     start_date: NaiveDate,
     end_date: NaiveDate,
-) -> anyhow::Result<(NaiveDate, NaiveDate, RowData)> {
-    let is_no_holiday = |d: &chrono::NaiveDate| !vacation_days.contains(d);
+) -> RowData {
+    let no_holiday = |d: &chrono::NaiveDate| working_share(vacation_days, d);
 
-    let is_no_holiday_and_not_absent =
-        |d: &chrono::NaiveDate| !vacation_days.contains(d) && !planned_absence.contains(d);
+    let workdays = s.working_time.count_days(start_date, end_date, &no_holiday);
+    let absence_by_kind = AbsenceKind::ALL
+        .iter()
+        .map(|kind| {
+            let absent = |d: &NaiveDate| absent_share(planned_absence, vacation_days, d, *kind);
+            (
+                *kind,
+                s.working_time.count_days(start_date, end_date, &absent),
+            )
+        })
+        .filter(|(_, days)| *days > 0.0)
+        .collect::<BTreeMap<_, _>>();
+    let days_of_absence = absence_by_kind
+        .iter()
+        .filter(|(kind, _)| kind.reduces_target())
+        .map(|(_, days)| days)
+        .sum::<f32>();
 
-    let workdays = count_weekdays(start_date, end_date, &is_no_holiday);
-    let days_of_absence =
-        workdays - count_weekdays(start_date, end_date, &is_no_holiday_and_not_absent);
+    let to_be_worked = |d: &NaiveDate| {
+        no_holiday(d)
+            - AbsenceKind::ALL
+                .iter()
+                .filter(|kind| kind.reduces_target())
+                .map(|kind| absent_share(planned_absence, vacation_days, d, *kind))
+                .sum::<f32>()
+    };
+    let work_hours = s
+        .working_time
+        .target_hours(start_date, end_date, &to_be_worked);
+    RowData {
+        workdays,
+        days_of_absence,
+        absence_by_kind,
+        work_hours,
+        ..RowData::default()
+    }
+}
 
-    let work_hours = (workdays - days_of_absence) as f32 * s.tz_factor;
-    Ok((
-        start_date,
-        end_date,
-        RowData {
-            workdays,
-            days_of_absence,
-            work_hours,
-            ..RowData::default()
-        } + row_data_from_redmine(start_date, end_date, &s, client.clone()).await?,
-    ))
+/// Difference between redmine hours and target hours in `from..to`, which might
+/// lie outside of the queried range
+async fn difference_between(
+    s: &Settings,
+    from: NaiveDate,
+    to: NaiveDate,
+    client: &LazyClient,
+) -> anyhow::Result<f32> {
+    if from >= to {
+        return Ok(0.0);
+    }
+    let settings = Settings {
+        from,
+        to: to.pred(),
+        check_holidays_online: false,
+        ..s.clone()
+    };
+    let vacation_days = get_vacation_days_for(&settings, client).await?;
+    let planned_absence =
+        absence::AbsenceConfig::load()?
+            .days_of_absence(settings.from, settings.to, &|d| s.working_time.hours(d));
+
+    let entries = fetch_entries(s, from, to, client).await?;
+    let (_, _, data) = compute_table_row(
+        &vacation_days,
+        &planned_absence,
+        &settings,
+        &entries,
+        from,
+        to,
+    );
+    Ok(data.redmine_hours - data.work_hours)
 }
 
-async fn get_vacation_days_for(s: &Settings) -> anyhow::Result<BTreeSet<NaiveDate>> {
-    let mut accum: BTreeSet<NaiveDate> = std::collections::BTreeSet::new();
-    for year in years_in_range(s.from, s.to) {
-        accum.extend(
-            feiertage::get_holidays(year, feiertage::Bundesland::NW)
-                .await
-                .with_context(|| format!("When querying holidays for {year}"))?
-                .keys(),
+/// Hours to book on each working day after today, so that the difference is zero
+/// at the end of the current month and week. With an opening balance, another row
+/// brings the overtime balance to zero at the end of the month.
+async fn print_forecast(s: &Settings, client: &LazyClient) -> anyhow::Result<()> {
+    let today = today();
+    let month = NaiveDate::from_ymd(today.year(), today.month(), 1);
+    let week = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+    let mut periods = vec![
+        (
+            format!("{}/{:02}", month.year(), month.month()),
+            month,
+            next_month(month),
+            None,
+        ),
+        (
+            format!("{} KW{:02}", week.iso_week().year(), week.iso_week().week()),
+            week,
+            next_week_monday(week),
+            None,
+        ),
+    ];
+    if let Some(balance) = s
+        .overtime_balances
+        .iter()
+        .rev()
+        .find(|balance| balance.date <= today)
+    {
+        periods.push((
+            format!("{}/{:02} bis Saldo 0", month.year(), month.month()),
+            month,
+            next_month(month),
+            Some(balance),
+        ));
+    }
+
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+    table.set_titles(row![
+        "Zeitraum",
+        "Differenz bisher",
+        "verbleibende Arbeitstage",
+        "verbleibende Sollstunden",
+        "Stunden pro Tag"
+    ]);
+
+    for (caption, from, to, opening_balance) in periods {
+        let settings = Settings {
+            from,
+            to: to.pred(),
+            check_holidays_online: false,
+            ..s.clone()
+        };
+        let vacation_days = get_vacation_days_for(&settings, client).await?;
+        let planned_absence =
+            absence::AbsenceConfig::load()?
+                .days_of_absence(from, to.pred(), &|d| s.working_time.hours(d));
+
+        let difference = match opening_balance {
+            Some(balance) => {
+                balance.hours + difference_between(s, balance.date, today.succ(), client).await?
+            }
+            None => {
+                let entries = fetch_entries(s, from, today.succ(), client).await?;
+                let (_, _, so_far) = compute_table_row(
+                    &vacation_days,
+                    &planned_absence,
+                    &settings,
+                    &entries,
+                    from,
+                    today.succ(),
+                );
+                so_far.redmine_hours - so_far.work_hours
+            }
+        };
+        let remaining = planned_row_data(
+            &vacation_days,
+            &planned_absence,
+            &settings,
+            today.succ(),
+            to,
         );
+        let days = remaining.workdays - remaining.days_of_absence;
+
+        table.add_row(Row::new(vec![
+            Cell::new(&caption).style_spec("i"),
+            fmt_cell(difference),
+            fmt_days(days),
+            fmt_cell(remaining.work_hours),
+            if days > 0.0 {
+                fmt_cell((remaining.work_hours - difference).max(0.0) / days)
+            } else {
+                Cell::new("-").style_spec("r")
+            },
+        ]));
+    }
+
+    println!("Prognose ab {}:", today.succ().format("%d.%m.%Y"));
+    table.printstd();
+    Ok(())
+}
+
+/// Share of the day which needs to be worked
+fn working_share(vacation_days: &Holidays, day: &NaiveDate) -> f32 {
+    1.0 - vacation_days
+        .get(day)
+        .map(|day_off| day_off.fraction)
+        .unwrap_or(0.0)
+}
+
+/// Days without working hours and whole holidays
+fn is_day_off(s: &Settings, vacation_days: &Holidays, day: &NaiveDate) -> bool {
+    !s.working_time.works_on(day) || working_share(vacation_days, day) <= 0.0
+}
+
+/// Share of the day spent on absences of kind `wanted`. An absence can't cover more
+/// than the part of the day which isn't free anyway.
+fn absent_share(
+    planned_absence: &Absences,
+    vacation_days: &Holidays,
+    day: &NaiveDate,
+    wanted: AbsenceKind,
+) -> f32 {
+    let mut left = working_share(vacation_days, day);
+    for (kind, share) in planned_absence.get(day).into_iter().flatten() {
+        let absent = share.min(left);
+        if *kind == wanted {
+            return absent;
+        }
+        left -= absent;
+    }
+    0.0
+}
+
+/// Holidays, closure days and partially free days. Holidays excluded by
+/// the configured rules are kept with a `fraction` of zero.
+async fn get_vacation_days_for(s: &Settings, client: &LazyClient) -> anyhow::Result<Holidays> {
+    let mut accum = Holidays::new();
+    let provider = s.region.provider()?;
+    let closure_calendars = s
+        .closure_calendars
+        .iter()
+        .map(|file| ical::Calendar::load(file))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for year in years_in_range(s.from, s.to) {
+        let holidays = provider
+            .holidays(year)
+            .with_context(|| format!("When computing holidays for {year}"))?;
+
+        if let (&Region::Germany(state), true) = (&s.region, s.check_holidays_online) {
+            let online = match client.get() {
+                Ok(client) => {
+                    feiertage::get_holidays_cached(year, state, client, s.refresh_holidays).await
+                }
+                Err(e) => Err(e),
+            };
+            match online {
+                Ok(online) => {
+                    for difference in feiertage::differences(&holidays, &online) {
+                        eprintln!("Warning: {difference}");
+                    }
+                }
+                Err(e) => eprintln!("Warning: could not cross-check holidays for {year}: {e:#}"),
+            }
+        }
+
+        let apply_rules = |(day, holiday): (NaiveDate, DayOff)| {
+            let fraction = if s.holiday_rules.applies(&holiday) {
+                holiday.fraction
+            } else {
+                0.0
+            };
+            (
+                day,
+                DayOff {
+                    fraction,
+                    ..holiday
+                },
+            )
+        };
+        accum.extend(holidays.into_iter().map(apply_rules));
+
+        for calendar in &closure_calendars {
+            accum.extend(calendar.holidays(year)?.into_iter().map(apply_rules));
+        }
+
+        for partial in &s.partial_holidays {
+            if let Some(day) = partial.date_in(year) {
+                // a holiday on the same day wins
+                accum.entry(day).or_insert_with(|| DayOff {
+                    name: partial.name.clone(),
+                    fraction: partial.fraction,
+                    note: None,
+                });
+            }
+        }
     }
     Ok(accum)
 }
 
 #[derive(Debug, Default, Clone)]
 struct RowData {
-    workdays: usize,
-    days_of_absence: usize,
+    workdays: f32,
+    /// Days of absence which reduce the target hours
+    days_of_absence: f32,
+    absence_by_kind: BTreeMap<AbsenceKind, f32>,
     work_hours: f32,
     redmine_hours: f32,
 }
@@ -206,59 +808,76 @@ struct RowData {
 impl std::ops::Add for RowData {
     type Output = Self;
     fn add(self, other: Self) -> Self {
+        let mut absence_by_kind = self.absence_by_kind;
+        for (kind, days) in other.absence_by_kind {
+            *absence_by_kind.entry(kind).or_insert(0.0) += days;
+        }
         RowData {
             workdays: self.workdays + other.workdays,
             days_of_absence: self.days_of_absence + other.days_of_absence,
+            absence_by_kind,
             work_hours: self.work_hours + other.work_hours,
             redmine_hours: self.redmine_hours + other.redmine_hours,
         }
     }
 }
 
-async fn row_data_from_redmine(
-    start_date: chrono::NaiveDate,
-    end_date: chrono::NaiveDate,
-    settings: &program_config::Settings,
-    client: reqwest::Client,
-) -> anyhow::Result<RowData> {
-    let redmine_hours = redmine::HoursSpent::range(
-        start_date,
-        end_date,
-        &settings.username,
-        &settings.password,
-        client,
-    )
-    .run()
-    .await?
-    .into_iter()
-    .fold(0.0, |v, time_res| v + time_res.hours);
-
-    Ok(RowData {
-        redmine_hours,
-        ..RowData::default()
-    })
-}
-
-fn make_row(caption: Cell, data: &RowData) -> Row {
-    Row::new(vec![
-        caption,
-        fmt_cell(data.workdays),
-        fmt_cell(data.days_of_absence),
-        fmt_cell(data.work_hours),
-        fmt_cell(data.redmine_hours),
-        fmt_cell(data.redmine_hours - data.work_hours),
-    ])
+/// Time entries in `from..to`
+async fn fetch_entries(
+    s: &Settings,
+    from: NaiveDate,
+    to: NaiveDate,
+    client: &LazyClient,
+) -> anyhow::Result<Vec<TimeEntry>> {
+    redmine::HoursSpent::range(from, to, &s.username, &s.password, client.get()?.clone())
+        .run()
+        .await
 }
 
-fn fmt_cell<T>(val: T) -> prettytable::Cell
-where
-    T: std::fmt::Display + PartialOrd + Default,
-{
-    let txt = format!("{:.2}", val);
+/// Hours spent in `start_date..end_date`, without the ones booked as absence
+/// (see [absence::AbsenceBooking]). Those are returned as share of the day instead.
+fn row_data_from_entries(
+    entries: &[TimeEntry],
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+    settings: &Settings,
+) -> (RowData, Absences) {
+    let mut redmine_hours = 0.0;
+    let mut booked_absence = Absences::new();
 
-    if val < T::default() {
-        Cell::new(&txt).style_spec("rFr")
-    } else {
-        Cell::new(&txt).style_spec("r")
+    for entry in entries
+        .iter()
+        .filter(|entry| (start_date..end_date).contains(&entry.spent_on))
+    {
+        match settings
+            .absence_bookings
+            .iter()
+            .find(|booking| booking.matches(entry.project.id, entry.activity.id))
+        {
+            Some(booking) => {
+                let target = settings.working_time.hours(&entry.spent_on);
+                if target <= 0.0 {
+                    eprintln!(
+                        "Warning: {} hours booked as absence on {}, which has no working hours, are not counted",
+                        entry.hours, entry.spent_on
+                    );
+                    continue;
+                }
+                *booked_absence
+                    .entry(entry.spent_on)
+                    .or_default()
+                    .entry(booking.kind)
+                    .or_insert(0.0) += entry.hours / target;
+            }
+            None => redmine_hours += entry.hours,
+        }
     }
+
+    (
+        RowData {
+            redmine_hours,
+            ..RowData::default()
+        },
+        booked_absence,
+    )
 }
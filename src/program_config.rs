@@ -1,7 +1,184 @@
+use crate::absence::{AbsenceBooking, AbsenceKind};
+use crate::csv::{parse_separator, CsvFormat};
+use crate::date_helper::Grouping;
+use crate::holidays::{HolidayRule, HolidayRules, PartialHoliday, Region};
+use crate::report::OutputFormat;
+use crate::schedule::{Contract, Schedule, WorkingTime};
+use crate::vacation::Entitlement;
+use anyhow::{anyhow, Context};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
 #[derive(Deserialize, Debug, Default)]
 struct Config {
     pub username: Option<String>,
     pub teilzeitfaktor: Option<f32>,
+    /// Hours per weekday, e.g. `{"Mo": 8, "Di": 8, "Mi": 8, "Do": 8, "Fr": 4}`
+    #[serde(default)]
+    pub schedule: BTreeMap<String, f32>,
+    #[serde(default)]
+    pub contracts: Vec<ContractConfig>,
+    pub employment_start: Option<chrono::NaiveDate>,
+    pub employment_end: Option<chrono::NaiveDate>,
+    pub bundesland: Option<String>,
+    pub land: Option<String>,
+    pub kanton: Option<String>,
+    pub feiertage_datei: Option<PathBuf>,
+    #[serde(default)]
+    pub closure_calendars: Vec<PathBuf>,
+    #[serde(default)]
+    pub partial_holidays: BTreeMap<String, PartialHolidayConfig>,
+    #[serde(default)]
+    pub show_holidays: bool,
+    pub regional_holidays: Option<HolidayRule>,
+    #[serde(default)]
+    pub holiday_rules: BTreeMap<String, HolidayRule>,
+    #[serde(default)]
+    pub check_holidays_online: bool,
+    pub vacation: Option<Entitlement>,
+    #[serde(default)]
+    pub absence_bookings: Vec<AbsenceBooking>,
+    #[serde(default)]
+    pub overtime_balances: Vec<OpeningBalance>,
+    /// Delimiter of csv output, German Excel needs `;`
+    pub csv_delimiter: Option<char>,
+    pub csv_decimal_separator: Option<char>,
+    #[serde(flatten)]
+    pub network: NetworkConfig,
+}
+
+/// Overtime balance confirmed by HR at the start of `date`, e.g. `{"date": "2018-01-01", "hours": 12.5}`
+#[derive(Deserialize, Debug, Clone)]
+pub struct OpeningBalance {
+    pub date: chrono::NaiveDate,
+    pub hours: f32,
+}
+
+/// Contract period, `schedule` and `teilzeitfaktor` default to the global ones
+#[derive(Deserialize, Debug)]
+struct ContractConfig {
+    first_day: chrono::NaiveDate,
+    last_day: Option<chrono::NaiveDate>,
+    teilzeitfaktor: Option<f32>,
+    #[serde(default)]
+    schedule: BTreeMap<String, f32>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum PartialHolidayConfig {
+    Fraction(f32),
+    Named { name: String, fraction: f32 },
+}
+
+/// Connection settings for reaching redmine (and the holiday API) from inside a company network.
+#[derive(Deserialize, Debug, Default, Clone)]
+pub struct NetworkConfig {
+    /// Additional PEM encoded CA certificates (or bundles) to trust
+    #[serde(default)]
+    pub ca_certificates: Vec<PathBuf>,
+    /// PEM encoded client certificate for mutual TLS, requires `client_key`
+    pub client_certificate: Option<PathBuf>,
+    /// PEM encoded (PKCS#8) private key belonging to `client_certificate`
+    pub client_key: Option<PathBuf>,
+    /// Proxy URL used for all requests, e.g. `http://proxy.company.x:3128`
+    pub proxy: Option<String>,
+    /// Comma separated list of hosts or networks to be reached without proxy
+    pub no_proxy: Option<String>,
+    /// Don't verify server certificates. Only ever use this for testing!
+    #[serde(default)]
+    pub insecure: bool,
+}
+
+impl NetworkConfig {
+    pub fn http_client(&self) -> Result<reqwest::Client, anyhow::Error> {
+        let mut builder = reqwest::Client::builder();
+
+        for ca_file in &self.ca_certificates {
+            let pem = std::fs::read(ca_file)
+                .with_context(|| format!("Could not read CA certificate {ca_file:#?}"))?;
+            for cert in ca_certificates(&pem)
+                .with_context(|| format!("Could not parse CA certificate {ca_file:#?}"))?
+            {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+
+        match (&self.client_certificate, &self.client_key) {
+            (Some(cert_file), Some(key_file)) => {
+                let cert = std::fs::read(cert_file)
+                    .with_context(|| format!("Could not read client certificate {cert_file:#?}"))?;
+                let key = std::fs::read(key_file)
+                    .with_context(|| format!("Could not read client key {key_file:#?}"))?;
+                let identity = reqwest::Identity::from_pkcs8_pem(&cert, &key)
+                    .with_context(|| format!("Could not load client certificate {cert_file:#?}"))?;
+                builder = builder.identity(identity);
+            }
+            (None, None) => {}
+            _ => {
+                return Err(anyhow!(
+                    "Both client_certificate and client_key need to be configured for mutual TLS"
+                ))
+            }
+        }
+
+        if let Some(proxy_url) = &self.proxy {
+            let proxy = reqwest::Proxy::all(proxy_url)
+                .with_context(|| format!("Invalid proxy url {proxy_url}"))?
+                .no_proxy(
+                    self.no_proxy
+                        .as_deref()
+                        .and_then(reqwest::NoProxy::from_string),
+                );
+            builder = builder.proxy(proxy);
+        }
+
+        if self.insecure {
+            eprintln!(
+                "Warning: certificate verification is disabled, never use this in production!"
+            );
+            builder = builder
+                .danger_accept_invalid_certs(true)
+                .danger_accept_invalid_hostnames(true);
+        }
+
+        builder
+            .build()
+            .with_context(|| "While setting up the http client")
+    }
+}
+
+/// Http client which is only built once a request is sent, so that commands
+/// working offline don't fail on broken network settings
+pub struct LazyClient {
+    network: NetworkConfig,
+    client: std::cell::OnceCell<reqwest::Client>,
+}
+
+impl LazyClient {
+    pub fn new(network: &NetworkConfig) -> LazyClient {
+        LazyClient {
+            network: network.clone(),
+            client: std::cell::OnceCell::new(),
+        }
+    }
+
+    pub fn get(&self) -> Result<&reqwest::Client, anyhow::Error> {
+        if let Some(client) = self.client.get() {
+            return Ok(client);
+        }
+        let client = self.network.http_client()?;
+        Ok(self.client.get_or_init(|| client))
+    }
+}
+
+/// All certificates of a PEM file, which might be a bundle
+fn ca_certificates(pem: &[u8]) -> Result<Vec<reqwest::Certificate>, anyhow::Error> {
+    let certs = reqwest::Certificate::from_pem_bundle(pem)?;
+    if certs.is_empty() {
+        return Err(anyhow!("No certificate found"));
+    }
+    Ok(certs)
 }
 
 fn read_config() -> Config {
@@ -27,7 +204,7 @@ fn read_config() -> Config {
 }
 
 fn get_settings_and_cmdline_parser() -> (clap::ArgMatches<'static>, Config) {
-    use clap::{App, Arg};
+    use clap::{App, Arg, SubCommand};
 
     let config = read_config();
 
@@ -38,6 +215,7 @@ fn get_settings_and_cmdline_parser() -> (clap::ArgMatches<'static>, Config) {
             .about("Zeigt die Soll- sowie die Ist-Stundenzahl an")
             .arg(
                 Arg::with_name("tf")
+                    .global(true)
                     .short("z")
                     .long("teilzeit")
                     .value_name("FAKTOR")
@@ -46,15 +224,16 @@ fn get_settings_and_cmdline_parser() -> (clap::ArgMatches<'static>, Config) {
             )
             .arg(
                 Arg::with_name("user")
+                    .global(true)
                     .short("u")
                     .long("username")
                     .value_name("USERNAME")
                     .help("Username for redmine login")
-                    .required(config.username.is_none())
                     .takes_value(true),
             )
             .arg(
                 Arg::with_name("from")
+                    .global(true)
                     .short("f")
                     .long("from")
                     .value_name("DATE")
@@ -63,63 +242,455 @@ fn get_settings_and_cmdline_parser() -> (clap::ArgMatches<'static>, Config) {
             )
             .arg(
                 Arg::with_name("to")
+                    .global(true)
                     .short("t")
                     .long("to")
                     .value_name("DATE")
                     .help("Enddatum für Zeitabfrage (einschließlich), Standard = Ende der Woche"),
             )
+            .arg(
+                Arg::with_name("bundesland")
+                    .global(true)
+                    .short("l")
+                    .long("bundesland")
+                    .value_name("LAND")
+                    .help("Bundesland für Feiertage, z.B. NW oder Bayern. Standard = NW")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("land")
+                    .global(true)
+                    .long("land")
+                    .value_name("LAND")
+                    .help("Land für Feiertage: DE, AT oder CH. Standard = DE")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("kanton")
+                    .global(true)
+                    .long("kanton")
+                    .value_name("KANTON")
+                    .help("Kanton für Schweizer Feiertage, z.B. ZH")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("holiday_file")
+                    .global(true)
+                    .long("feiertage-datei")
+                    .value_name("DATEI")
+                    .help("Feiertage aus einer JSON-Datei statt Land/Bundesland")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("ics")
+                    .global(true)
+                    .long("ics")
+                    .value_name("DATEI")
+                    .help("Zusätzliche freie Tage (z.B. Betriebsruhe) aus iCalendar-Datei")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1),
+            )
+            .arg(
+                Arg::with_name("check_holidays")
+                    .global(true)
+                    .long("feiertage-api")
+                    .help("Berechnete Feiertage mit feiertage-api.de abgleichen"),
+            )
+            .arg(
+                Arg::with_name("refresh_holidays")
+                    .global(true)
+                    .long("refresh-holidays")
+                    .help("Feiertage neu von feiertage-api.de laden, statt aus dem Cache"),
+            )
+            .arg(
+                Arg::with_name("show_holidays")
+                    .global(true)
+                    .long("show-holidays")
+                    .help("Spalte mit den Feiertagen je Zeitraum anzeigen"),
+            )
+            .arg(
+                Arg::with_name("format")
+                    .long("format")
+                    .value_name("FORMAT")
+                    .help("Ausgabeformat des Berichts")
+                    .possible_values(&["table", "json", "csv", "markdown", "html"])
+                    .default_value("table"),
+            )
+            .arg(
+                Arg::with_name("group_by")
+                    .long("group-by")
+                    .value_name("ZEITRAUM")
+                    .help("Zeilen je Tag, Woche (ISO), Monat, Quartal oder Jahr. Standard = Monate, dann Wochen, dann Resttage")
+                    .possible_values(&["auto", "day", "week", "month", "quarter", "year"])
+                    .default_value("auto"),
+            )
+            .arg(
+                Arg::with_name("delimiter")
+                    .global(true)
+                    .long("delimiter")
+                    .value_name("ZEICHEN")
+                    .help("Trennzeichen für csv, z.B. ';' oder tab. Standard = ','")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("decimal_separator")
+                    .global(true)
+                    .long("decimal-separator")
+                    .value_name("ZEICHEN")
+                    .help("Dezimaltrennzeichen für csv, z.B. ','. Standard = '.'")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("proxy")
+                    .global(true)
+                    .long("proxy")
+                    .value_name("URL")
+                    .help("HTTP-Proxy für alle Verbindungen")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("insecure")
+                    .global(true)
+                    .short("k")
+                    .long("insecure")
+                    .help("Zertifikate nicht prüfen (nur zum Testen!)"),
+            )
+            .subcommand(
+                SubCommand::with_name("holidays")
+                    .about("Listet die Feiertage und freien Tage im Zeitraum auf"),
+            )
+            .subcommand(
+                SubCommand::with_name("vacation")
+                    .about("Zeigt genommenen, geplanten und verbleibenden Urlaub pro Jahr"),
+            )
+            .subcommand(
+                SubCommand::with_name("entries")
+                    .about("Exportiert alle Zeiteinträge aus redmine im Zeitraum als csv"),
+            )
+            .subcommand(
+                SubCommand::with_name("absence")
+                    .about("Verwaltet die Abwesenheiten in absence.json")
+                    .setting(clap::AppSettings::SubcommandRequiredElseHelp)
+                    .subcommand(
+                        SubCommand::with_name("add")
+                            .about("Trägt eine Abwesenheit von --from bis --to ein")
+                            .arg(
+                                Arg::with_name("reason")
+                                    .short("r")
+                                    .long("reason")
+                                    .value_name("NAME")
+                                    .help("Bezeichnung, z.B. Sommerurlaub")
+                                    .takes_value(true)
+                                    .required(true),
+                            )
+                            .arg(
+                                Arg::with_name("kind")
+                                    .long("kind")
+                                    .value_name("ART")
+                                    .help("Art der Abwesenheit, z.B. vacation oder sick. Standard = vacation")
+                                    .takes_value(true),
+                            ),
+                    )
+                    .subcommand(
+                        SubCommand::with_name("list")
+                            .about("Listet die Abwesenheiten auf")
+                            .arg(
+                                Arg::with_name("year")
+                                    .long("year")
+                                    .value_name("JAHR")
+                                    .help("Nur Abwesenheiten in diesem Jahr")
+                                    .takes_value(true),
+                            ),
+                    )
+                    .subcommand(
+                        SubCommand::with_name("check")
+                            .about("Prüft absence.json auf unplausible Einträge"),
+                    )
+                    .subcommand(
+                        SubCommand::with_name("remove")
+                            .about("Entfernt eine Abwesenheit")
+                            .arg(
+                                Arg::with_name("name")
+                                    .value_name("NAME")
+                                    .help("Bezeichnung der Abwesenheit")
+                                    .required(true),
+                            ),
+                    ),
+            )
             .get_matches(),
         config,
     )
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    /// Table of target and actual hours
+    Report,
+    /// List of holidays in the given range
+    Holidays,
+    /// Vacation balance for the years in the given range
+    Vacation,
+    /// Time entries from redmine as csv
+    Entries,
+    /// Changes to or listing of `absence.json`
+    Absence(AbsenceCommand),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AbsenceCommand {
+    Add {
+        name: String,
+        kind: AbsenceKind,
+        first_day: chrono::NaiveDate,
+        last_day: chrono::NaiveDate,
+    },
+    List {
+        year: Option<i32>,
+    },
+    Remove {
+        name: String,
+    },
+    Check,
+}
+
+impl Command {
+    fn needs_redmine(&self) -> bool {
+        matches!(self, Command::Report | Command::Entries)
+    }
+}
+
 #[derive(Clone)]
 pub struct Settings {
+    pub command: Command,
     pub from: chrono::NaiveDate,
     pub to: chrono::NaiveDate,
-    pub tz_factor: f32,
+    /// Working hours of each day, already scaled by the part-time factor
+    pub working_time: WorkingTime,
+    pub region: Region,
+    pub closure_calendars: Vec<PathBuf>,
+    pub partial_holidays: Vec<PartialHoliday>,
+    pub holiday_rules: HolidayRules,
+    pub check_holidays_online: bool,
+    pub refresh_holidays: bool,
+    pub show_holidays: bool,
+    pub format: OutputFormat,
+    pub grouping: Grouping,
+    pub csv: CsvFormat,
+    pub vacation: Option<Entitlement>,
+    pub absence_bookings: Vec<AbsenceBooking>,
+    /// Sorted by date, the latest one before the end of the report is used
+    pub overtime_balances: Vec<OpeningBalance>,
     pub username: String,
     pub password: String,
+    pub network: NetworkConfig,
 }
 
 pub fn get_settings() -> Result<Settings, anyhow::Error> {
     let (matches, config) = get_settings_and_cmdline_parser();
 
+    let command = match matches.subcommand() {
+        ("holidays", _) => Command::Holidays,
+        ("vacation", _) => Command::Vacation,
+        ("entries", _) => Command::Entries,
+        ("absence", Some(absence)) => Command::Absence(absence_command(absence)?),
+        _ => Command::Report,
+    };
+
     let (from, to) = month_span_from_args(&matches)?;
 
-    let tz_factor = 8.0
-        * matches
-            .value_of("tf")
-            .map(|v| v.parse::<f32>().unwrap())
-            .or(config.teilzeitfaktor)
-            .unwrap_or(1.0);
+    // --teilzeit overrides the factors of all contracts
+    let tz_factor = matches.value_of("tf").map(|v| v.parse::<f32>().unwrap());
+    let default_schedule = if config.schedule.is_empty() {
+        Schedule::default()
+    } else {
+        Schedule::from_names(&config.schedule)?
+    };
+    let contracts = config
+        .contracts
+        .iter()
+        .map(|contract| {
+            let schedule = if contract.schedule.is_empty() {
+                default_schedule.clone()
+            } else {
+                Schedule::from_names(&contract.schedule)?
+            };
+            let factor = tz_factor
+                .or(contract.teilzeitfaktor)
+                .or(config.teilzeitfaktor)
+                .unwrap_or(1.0);
+            Ok(Contract {
+                first_day: contract.first_day,
+                last_day: contract.last_day,
+                schedule: schedule.scaled(factor),
+            })
+        })
+        .collect::<Result<Vec<_>, anyhow::Error>>()?;
+    let working_time = WorkingTime::new(
+        default_schedule.scaled(tz_factor.or(config.teilzeitfaktor).unwrap_or(1.0)),
+        contracts,
+        config.employment_start,
+        config.employment_end,
+    )?;
+
+    let region = Region::from_names(
+        matches.value_of("land").or(config.land.as_deref()),
+        matches
+            .value_of("bundesland")
+            .or(config.bundesland.as_deref()),
+        matches.value_of("kanton").or(config.kanton.as_deref()),
+        matches
+            .value_of("holiday_file")
+            .map(Path::new)
+            .or(config.feiertage_datei.as_deref()),
+    )?;
+
+    let mut closure_calendars = config.closure_calendars;
+    if let Some(files) = matches.values_of("ics") {
+        closure_calendars.extend(files.map(PathBuf::from));
+    }
+
+    let partial_holidays = config
+        .partial_holidays
+        .iter()
+        .map(|(date, partial)| match partial {
+            PartialHolidayConfig::Fraction(fraction) => {
+                PartialHoliday::parse(date, "Teilweise frei", *fraction)
+            }
+            PartialHolidayConfig::Named { name, fraction } => {
+                PartialHoliday::parse(date, name, *fraction)
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let holiday_rules = HolidayRules {
+        regional: config.regional_holidays.unwrap_or(HolidayRule::Include),
+        by_name: config.holiday_rules,
+    };
+
+    if config
+        .absence_bookings
+        .iter()
+        .any(|booking| booking.project.is_none() && booking.activity.is_none())
+    {
+        return Err(anyhow!(
+            "Each entry of absence_bookings needs a project or an activity"
+        ));
+    }
+
+    let mut overtime_balances = config.overtime_balances;
+    overtime_balances.sort_by_key(|balance| balance.date);
+
+    let refresh_holidays = matches.is_present("refresh_holidays");
+    let check_holidays_online =
+        matches.is_present("check_holidays") || config.check_holidays_online || refresh_holidays;
+    let show_holidays = matches.is_present("show_holidays") || config.show_holidays;
+    let format = matches.value_of("format").unwrap_or("table").parse()?;
+    let grouping = matches.value_of("group_by").unwrap_or("auto").parse()?;
+    let default_csv = CsvFormat::default();
+    let csv = CsvFormat {
+        delimiter: match matches.value_of("delimiter") {
+            Some(value) => parse_separator(value)?,
+            None => config.csv_delimiter.unwrap_or(default_csv.delimiter),
+        },
+        decimal_separator: match matches.value_of("decimal_separator") {
+            Some(value) => parse_separator(value)?,
+            None => config
+                .csv_decimal_separator
+                .unwrap_or(default_csv.decimal_separator),
+        },
+    };
+
+    let mut network = config.network;
+    if let Some(proxy) = matches.value_of("proxy") {
+        network.proxy = Some(proxy.to_owned());
+    }
+    network.insecure |= matches.is_present("insecure");
 
     let service = env!("REDMINE_SERVER_NAME"); // TODO: This should really be a conf
     let username = matches
         .value_of("user")
         .map(|v| v.to_owned())
-        .or(config.username)
-        .unwrap();
-
-    let password = {
-        let keyring = keyring::Entry::new(&service, &username);
-
-        keyring.get_password().unwrap_or_else(|_| {
-            let pw = rpassword::prompt_password_stderr(&format!("Password for {}: ", &username))
-                .unwrap();
-            keyring.set_password(&pw).unwrap_or_else(|e| {
-                println!("Couldn't store password to keyring, I'm sorry: {}", e)
-            });
-            pw
-        })
+        .or(config.username);
+
+    let (username, password) = if command.needs_redmine() {
+        let username = username.ok_or_else(|| {
+            anyhow!("No redmine username given, use --username or configure it in config.json")
+        })?;
+        let password = {
+            let keyring = keyring::Entry::new(&service, &username);
+
+            keyring.get_password().unwrap_or_else(|_| {
+                let pw =
+                    rpassword::prompt_password_stderr(&format!("Password for {}: ", &username))
+                        .unwrap();
+                keyring.set_password(&pw).unwrap_or_else(|e| {
+                    println!("Couldn't store password to keyring, I'm sorry: {}", e)
+                });
+                pw
+            })
+        };
+        (username, password)
+    } else {
+        (username.unwrap_or_default(), String::new())
     };
 
     Ok(Settings {
+        command,
         from,
         to,
-        tz_factor,
+        working_time,
+        region,
+        closure_calendars,
+        partial_holidays,
+        holiday_rules,
+        check_holidays_online,
+        refresh_holidays,
+        show_holidays,
+        format,
+        grouping,
+        csv,
+        vacation: config.vacation,
+        absence_bookings: config.absence_bookings,
+        overtime_balances,
         username,
         password,
+        network,
+    })
+}
+
+fn absence_command(matches: &clap::ArgMatches) -> Result<AbsenceCommand, anyhow::Error> {
+    Ok(match matches.subcommand() {
+        ("add", Some(add)) => {
+            let first_day = add
+                .value_of("from")
+                .ok_or_else(|| anyhow!("Please give the first day of absence with --from"))?
+                .parse()?;
+            AbsenceCommand::Add {
+                name: add.value_of("reason").unwrap_or_default().to_owned(),
+                kind: add
+                    .value_of("kind")
+                    .map(|v| v.parse())
+                    .unwrap_or(Ok(AbsenceKind::default()))?,
+                first_day,
+                last_day: add
+                    .value_of("to")
+                    .map(|v| v.parse())
+                    .unwrap_or(Ok(first_day))?,
+            }
+        }
+        ("list", Some(list)) => AbsenceCommand::List {
+            year: list
+                .value_of("year")
+                .map(|v| v.parse())
+                .transpose()
+                .context("Invalid year")?,
+        },
+        ("remove", Some(remove)) => AbsenceCommand::Remove {
+            name: remove.value_of("name").unwrap_or_default().to_owned(),
+        },
+        ("check", _) => AbsenceCommand::Check,
+        (name, _) => return Err(anyhow!("Unknown absence command '{name}'")),
     })
 }
 
@@ -139,3 +710,32 @@ fn month_span_from_args(
 
     Ok((from, to))
 }
+
+#[test]
+fn test_ca_bundle() {
+    let bundle = "-----BEGIN CERTIFICATE-----\n\
+MIIBfTCCASOgAwIBAgIUU0vqBdJpUZMcLQknII5/WasqNhgwCgYIKoZIzj0EAwIw\n\
+FDESMBAGA1UEAwwJVGVzdCBDQSAxMB4XDTI2MTAxODIwMzU0MVoXDTM2MTAxNTIw\n\
+MzU0MVowFDESMBAGA1UEAwwJVGVzdCBDQSAxMFkwEwYHKoZIzj0CAQYIKoZIzj0D\n\
+AQcDQgAE5WO+QFeQ8ZhxsPyhtTL4+014UuWPRYMkIGP0bAZ3NzhRIuOBKJSyXwVe\n\
+Lk2aDQXBGWCeG+6G9muF5RrH735K56NTMFEwHQYDVR0OBBYEFNC8nS2Hkmled/ii\n\
+2KrVJBogrfgKMB8GA1UdIwQYMBaAFNC8nS2Hkmled/ii2KrVJBogrfgKMA8GA1Ud\n\
+EwEB/wQFMAMBAf8wCgYIKoZIzj0EAwIDSAAwRQIgO1oqKcwQcxhLw8/zo+rYs47w\n\
+TmgdjUMdHur3AzV/L6gCIQDbDQHy1FE4zHY8ne21fGYbHpdfJnESuRgImrtSDQ3R\n\
+4w==\n\
+-----END CERTIFICATE-----\n\
+-----BEGIN CERTIFICATE-----\n\
+MIIBfTCCASOgAwIBAgIUOB3YWNrfQ/MDBCxu0MJARomg7xkwCgYIKoZIzj0EAwIw\n\
+FDESMBAGA1UEAwwJVGVzdCBDQSAyMB4XDTI2MTAxODIwMzU0MVoXDTM2MTAxNTIw\n\
+MzU0MVowFDESMBAGA1UEAwwJVGVzdCBDQSAyMFkwEwYHKoZIzj0CAQYIKoZIzj0D\n\
+AQcDQgAEAY4S8iPMhknFDnggF9EkzcmPVOASsDM1dF+MWMp8s7+YN4i/ZGU95YwP\n\
+RwtK7EXd90yrkjaD41eD9QcGnx6yL6NTMFEwHQYDVR0OBBYEFMaNnt8qAGmvQxtA\n\
+fwWKxaHAZeJPMB8GA1UdIwQYMBaAFMaNnt8qAGmvQxtAfwWKxaHAZeJPMA8GA1Ud\n\
+EwEB/wQFMAMBAf8wCgYIKoZIzj0EAwIDSAAwRQIgYb+z6C9k7D5bcpuhpHp2YsJH\n\
+CQC2CPzuqrNncSmlf8wCIQCvHAcVMuHt8QqhNgTssjp3QX1k+jfTSfF3CdOlwxoe\n\
+Jg==\n\
+-----END CERTIFICATE-----\n\
+";
+    assert_eq!(ca_certificates(bundle.as_bytes()).unwrap().len(), 2);
+    assert!(ca_certificates(b"no certificate").is_err());
+}
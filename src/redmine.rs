@@ -32,7 +32,7 @@ pub struct TimeEntry {
 #[derive(Deserialize, Debug)]
 #[allow(unused)]
 pub struct Issue {
-    id: u64,
+    pub id: u64,
 }
 
 #[derive(Deserialize, Debug)]
@@ -45,15 +45,15 @@ pub struct User {
 #[derive(Deserialize, Debug)]
 #[allow(unused)]
 pub struct Project {
-    id: u64,
-    name: String,
+    pub id: u64,
+    pub name: String,
 }
 
 #[derive(Deserialize, Debug)]
 #[allow(unused)]
 pub struct Activity {
-    id: u64,
-    name: String,
+    pub id: u64,
+    pub name: String,
 }
 
 pub struct HoursSpent {
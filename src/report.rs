@@ -0,0 +1,527 @@
+use crate::absence::AbsenceKind;
+use crate::csv::CsvFormat;
+use crate::date_helper::Grouping;
+use crate::program_config::OpeningBalance;
+use anyhow::{anyhow, Context};
+use chrono::NaiveDate;
+use prettytable::{format, Cell, Row, Table};
+use std::collections::BTreeMap;
+
+/// How the report is printed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Json,
+    Csv,
+    Markdown,
+    Html,
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(OutputFormat::Table),
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            "markdown" => Ok(OutputFormat::Markdown),
+            "html" => Ok(OutputFormat::Html),
+            _ => Err(anyhow!("Unknown output format '{s}'")),
+        }
+    }
+}
+
+/// Target and actual hours of one period, `end` is included
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Period {
+    pub caption: String,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub workdays: f32,
+    /// Days of absence which reduce the target hours
+    pub absence_days: f32,
+    pub absence_by_kind: BTreeMap<AbsenceKind, f32>,
+    pub target_hours: f32,
+    pub redmine_hours: f32,
+    pub difference: f32,
+    /// Difference accumulated up to the end of the period
+    pub cumulative: f32,
+    /// Names of the holidays on working days
+    pub holidays: Vec<String>,
+}
+
+/// Overtime balance at the end of the report
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Balance {
+    pub opening_date: NaiveDate,
+    pub opening_hours: f32,
+    pub hours: f32,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Report {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub periods: Vec<Period>,
+    pub total: Period,
+    pub balance: Option<Balance>,
+    #[serde(skip)]
+    pub grouping: Grouping,
+}
+
+impl Report {
+    /// Only kinds of absence which occur get a column
+    fn kinds(&self) -> Vec<AbsenceKind> {
+        AbsenceKind::ALL
+            .iter()
+            .copied()
+            .filter(|kind| {
+                self.periods
+                    .iter()
+                    .any(|period| period.absence_by_kind.contains_key(kind))
+            })
+            .collect()
+    }
+
+    fn titles(&self, kinds: &[AbsenceKind], show_holidays: bool) -> Vec<&'static str> {
+        let period = match self.grouping {
+            Grouping::Auto | Grouping::Month => "Monat",
+            Grouping::Day => "Tag",
+            Grouping::Week => "Woche",
+            Grouping::Quarter => "Quartal",
+            Grouping::Year => "Jahr",
+        };
+        let mut titles = vec![period, "Arbeitstage", "davon Abwesend"];
+        titles.extend(kinds.iter().map(|kind| kind.name()));
+        titles.extend(["Sollstunden", "Redmine-Stunden", "Differenz", "Kumuliert"]);
+        if show_holidays {
+            titles.push("Feiertage");
+        }
+        titles
+    }
+
+    /// Rows shown in the table, `None` separates the total and balance
+    fn lines(&self, kinds: &[AbsenceKind], show_holidays: bool) -> Vec<Option<Vec<Field>>> {
+        let mut lines = Vec::new();
+
+        // without grouping, single days are left over at the end of the range,
+        // they only count for the total
+        for period in self
+            .periods
+            .iter()
+            .filter(|period| self.grouping != Grouping::Auto || period.end > period.start)
+        {
+            let mut fields = period_fields(Field::Caption(period.caption.clone()), period, kinds);
+            if show_holidays {
+                fields.push(Field::Text(period.holidays.join(", ")));
+            }
+            lines.push(Some(fields));
+        }
+
+        if self.periods.len() > 1 {
+            lines.push(None);
+            lines.push(Some(period_fields(
+                Field::Total(self.total.caption.clone()),
+                &self.total,
+                kinds,
+            )));
+        }
+
+        if let Some(balance) = &self.balance {
+            let mut fields = vec![Field::Total("Saldo".to_owned())];
+            fields.extend((0..kinds.len() + 5).map(|_| Field::Text(String::new())));
+            fields.push(Field::Hours(balance.hours));
+            lines.push(None);
+            lines.push(Some(fields));
+        }
+
+        lines
+    }
+
+    pub fn to_table(&self, show_holidays: bool) -> Table {
+        let kinds = self.kinds();
+
+        let mut table = Table::new();
+        table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+        table.set_titles(Row::new(
+            self.titles(&kinds, show_holidays)
+                .into_iter()
+                .map(Cell::new)
+                .collect(),
+        ));
+
+        for line in self.lines(&kinds, show_holidays) {
+            match line {
+                Some(fields) => {
+                    table.add_row(Row::new(fields.iter().map(Field::to_cell).collect()));
+                }
+                None => {
+                    table.add_empty_row();
+                }
+            }
+        }
+
+        table
+    }
+
+    /// Negative values are bold, as markdown has no colors
+    pub fn to_markdown(&self, show_holidays: bool) -> String {
+        let kinds = self.kinds();
+        let titles = self.titles(&kinds, show_holidays);
+
+        let mut markdown = format!("| {} |\n", titles.join(" | "));
+        let alignment = (0..titles.len())
+            .map(|column| {
+                if column == 0 || (show_holidays && column == titles.len() - 1) {
+                    ":--"
+                } else {
+                    "--:"
+                }
+            })
+            .collect::<Vec<_>>();
+        markdown += &format!("| {} |\n", alignment.join(" | "));
+
+        for fields in self.lines(&kinds, show_holidays).into_iter().flatten() {
+            let mut cells = fields.iter().map(Field::to_markdown).collect::<Vec<_>>();
+            cells.resize(titles.len(), String::new());
+            markdown += &format!("| {} |\n", cells.join(" | "));
+        }
+        markdown
+    }
+
+    /// Standalone html page, negative values are red
+    pub fn to_html(&self, show_holidays: bool) -> String {
+        let kinds = self.kinds();
+        let titles = self.titles(&kinds, show_holidays);
+        let heading = format!(
+            "Stunden vom {} bis {}",
+            self.from.format("%d.%m.%Y"),
+            self.to.format("%d.%m.%Y")
+        );
+
+        let mut html = String::from("<!DOCTYPE html>\n<html lang=\"de\">\n<head>\n");
+        html += "<meta charset=\"utf-8\">\n";
+        html += &format!("<title>{heading}</title>\n");
+        html += "<style>\n\
+                 table { border-collapse: collapse; }\n\
+                 th, td { padding: 2px 8px; border-bottom: 1px solid #ddd; }\n\
+                 td.number { text-align: right; }\n\
+                 td.negative { color: red; }\n\
+                 tr.separator td { border: none; height: 1em; }\n\
+                 </style>\n</head>\n<body>\n";
+        html += &format!("<h1>{heading}</h1>\n<table>\n<thead>\n<tr>");
+        for title in &titles {
+            html += &format!("<th>{}</th>", escape_html(title));
+        }
+        html += "</tr>\n</thead>\n<tbody>\n";
+
+        for line in self.lines(&kinds, show_holidays) {
+            match line {
+                Some(fields) => {
+                    html += "<tr>";
+                    for field in &fields {
+                        html += &field.to_html();
+                    }
+                    html += "</tr>\n";
+                }
+                None => {
+                    html += &format!(
+                        "<tr class=\"separator\"><td colspan=\"{}\"></td></tr>\n",
+                        titles.len()
+                    )
+                }
+            }
+        }
+        html += "</tbody>\n</table>\n</body>\n</html>\n";
+        html
+    }
+
+    /// All periods including single days, followed by the total and balance
+    pub fn to_csv(&self, format: &CsvFormat) -> String {
+        let kinds = self.kinds();
+
+        let mut titles = vec![
+            "Zeitraum",
+            "Beginn",
+            "Ende",
+            "Arbeitstage",
+            "davon Abwesend",
+        ];
+        titles.extend(kinds.iter().map(|kind| kind.name()));
+        titles.extend([
+            "Sollstunden",
+            "Redmine-Stunden",
+            "Differenz",
+            "Kumuliert",
+            "Feiertage",
+        ]);
+        let mut csv = format.line(&titles);
+
+        for period in self.periods.iter().chain([&self.total]) {
+            let mut fields = vec![
+                period.caption.clone(),
+                period.start.to_string(),
+                period.end.to_string(),
+                format.number(period.workdays),
+                format.number(period.absence_days),
+            ];
+            fields.extend(kinds.iter().map(|kind| {
+                format.number(period.absence_by_kind.get(kind).copied().unwrap_or(0.0))
+            }));
+            fields.extend([
+                format.number(period.target_hours),
+                format.number(period.redmine_hours),
+                format.number(period.difference),
+                format.number(period.cumulative),
+                period.holidays.join(", "),
+            ]);
+            csv += &format.line(&fields);
+        }
+
+        if let Some(balance) = &self.balance {
+            let mut fields = vec![String::new(); titles.len()];
+            fields[0] = "Saldo".to_owned();
+            fields[2] = self.to.to_string();
+            fields[titles.len() - 2] = format.number(balance.hours);
+            csv += &format.line(&fields);
+        }
+        csv
+    }
+
+    pub fn to_json(&self) -> Result<String, anyhow::Error> {
+        serde_json::to_string_pretty(self).with_context(|| "While writing the report as json")
+    }
+}
+
+/// Cell of the report, independent of the output format
+#[derive(Debug, Clone, PartialEq)]
+enum Field {
+    Caption(String),
+    Total(String),
+    Text(String),
+    Days(f32),
+    Hours(f32),
+}
+
+impl Field {
+    fn to_cell(&self) -> Cell {
+        match self {
+            Field::Caption(text) => Cell::new(text).style_spec("i"),
+            Field::Total(text) => Cell::new(text).style_spec("b"),
+            Field::Text(text) => Cell::new(text),
+            Field::Days(days) => fmt_days(*days),
+            Field::Hours(hours) => fmt_cell(*hours),
+        }
+    }
+
+    fn to_markdown(&self) -> String {
+        let escape = |text: &str| text.replace('|', "\\|");
+        match self {
+            Field::Caption(text) => format!("*{}*", escape(text)),
+            Field::Total(text) => format!("**{}**", escape(text)),
+            Field::Text(text) => escape(text),
+            Field::Days(days) => format!("{}", days),
+            Field::Hours(hours) if *hours < 0.0 => format!("**{:.2}**", hours),
+            Field::Hours(hours) => format!("{:.2}", hours),
+        }
+    }
+
+    fn to_html(&self) -> String {
+        match self {
+            Field::Caption(text) => format!("<td><i>{}</i></td>", escape_html(text)),
+            Field::Total(text) => format!("<td><b>{}</b></td>", escape_html(text)),
+            Field::Text(text) => format!("<td>{}</td>", escape_html(text)),
+            Field::Days(days) => format!("<td class=\"number\">{}</td>", days),
+            Field::Hours(hours) if *hours < 0.0 => {
+                format!("<td class=\"number negative\">{:.2}</td>", hours)
+            }
+            Field::Hours(hours) => format!("<td class=\"number\">{:.2}</td>", hours),
+        }
+    }
+}
+
+/// Difference accumulated up to the end of each of the `periods` (first day, day after
+/// the last one, difference). It starts at `start` and is reset by each balance within
+/// the periods, also in the middle of one. `difference` computes the difference in `from..to`.
+pub fn cumulate(
+    periods: &[(NaiveDate, NaiveDate, f32)],
+    start: f32,
+    balances: &[OpeningBalance],
+    difference: &dyn Fn(NaiveDate, NaiveDate) -> f32,
+) -> Vec<f32> {
+    let mut cumulative = start;
+    periods
+        .iter()
+        .map(|(from, to, period_difference)| {
+            match balances
+                .iter()
+                .rev()
+                .find(|balance| (*from..*to).contains(&balance.date))
+            {
+                Some(balance) => cumulative = balance.hours + difference(balance.date, *to),
+                None => cumulative += period_difference,
+            }
+            cumulative
+        })
+        .collect()
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn period_fields(caption: Field, period: &Period, kinds: &[AbsenceKind]) -> Vec<Field> {
+    let mut fields = vec![
+        caption,
+        Field::Days(period.workdays),
+        Field::Days(period.absence_days),
+    ];
+    fields.extend(
+        kinds
+            .iter()
+            .map(|kind| Field::Days(period.absence_by_kind.get(kind).copied().unwrap_or(0.0))),
+    );
+    fields.extend([
+        Field::Hours(period.target_hours),
+        Field::Hours(period.redmine_hours),
+        Field::Hours(period.difference),
+        Field::Hours(period.cumulative),
+    ]);
+    fields
+}
+
+pub fn fmt_cell<T>(val: T) -> prettytable::Cell
+where
+    T: std::fmt::Display + PartialOrd + Default,
+{
+    let txt = format!("{:.2}", val);
+
+    if val < T::default() {
+        Cell::new(&txt).style_spec("rFr")
+    } else {
+        Cell::new(&txt).style_spec("r")
+    }
+}
+
+/// Days are usually whole, so skip the decimals unless there are half days
+pub fn fmt_days(val: f32) -> prettytable::Cell {
+    Cell::new(&format!("{}", val)).style_spec("r")
+}
+
+#[cfg(test)]
+fn example_report() -> Report {
+    let day = |d| NaiveDate::from_ymd(2018, 10, d);
+    let period = Period {
+        caption: "2018 KW42".to_owned(),
+        start: day(15),
+        end: day(19),
+        workdays: 5.0,
+        absence_days: 1.0,
+        absence_by_kind: [(AbsenceKind::Sick, 1.0)].into_iter().collect(),
+        target_hours: 32.0,
+        redmine_hours: 30.0,
+        difference: -2.0,
+        cumulative: -2.0,
+        holidays: vec![],
+    };
+    Report {
+        from: day(15),
+        to: day(19),
+        periods: vec![period.clone()],
+        total: Period {
+            caption: "Gesamt".to_owned(),
+            ..period
+        },
+        balance: None,
+        grouping: Grouping::Auto,
+    }
+}
+
+#[test]
+fn test_json() {
+    let report = example_report();
+    let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+    let period = &json["periods"][0];
+    assert_eq!(period["start"], "2018-10-15");
+    assert_eq!(period["end"], "2018-10-19");
+    assert_eq!(period["workdays"], 5.0);
+    assert_eq!(period["absence_days"], 1.0);
+    assert_eq!(period["absence_by_kind"]["sick"], 1.0);
+    assert_eq!(period["target_hours"], 32.0);
+    assert_eq!(period["redmine_hours"], 30.0);
+    assert_eq!(period["difference"], -2.0);
+    assert_eq!(json["total"]["caption"], "Gesamt");
+    assert!(json["balance"].is_null());
+}
+
+#[test]
+fn test_markdown_and_html() {
+    let report = Report {
+        balance: Some(Balance {
+            opening_date: NaiveDate::from_ymd(2018, 1, 1),
+            opening_hours: 1.0,
+            hours: 3.5,
+        }),
+        ..example_report()
+    };
+
+    let markdown = report.to_markdown(false);
+    let lines = markdown.lines().collect::<Vec<_>>();
+    assert_eq!(
+        lines[0],
+        "| Monat | Arbeitstage | davon Abwesend | Krank | Sollstunden | Redmine-Stunden | Differenz | Kumuliert |"
+    );
+    assert_eq!(
+        lines[2],
+        "| *2018 KW42* | 5 | 1 | 1 | 32.00 | 30.00 | **-2.00** | **-2.00** |"
+    );
+    assert_eq!(lines[3], "| **Saldo** |  |  |  |  |  |  | 3.50 |");
+
+    let html = report.to_html(true);
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<td class=\"number negative\">-2.00</td>"));
+    assert!(html.contains("<td class=\"number\">3.50</td>"));
+}
+
+#[test]
+fn test_cumulate() {
+    let day = |m, d| NaiveDate::from_ymd(2018, m, d);
+    // one hour of overtime per day
+    let difference = |from: NaiveDate, to: NaiveDate| (to - from).num_days() as f32;
+    let periods = [(6, 7), (7, 8), (8, 9), (9, 10)]
+        .iter()
+        .map(|(m, next)| {
+            (
+                day(*m, 1),
+                day(*next, 1),
+                difference(day(*m, 1), day(*next, 1)),
+            )
+        })
+        .collect::<Vec<_>>();
+    let balances = [
+        OpeningBalance {
+            date: day(1, 1),
+            hours: 10.0,
+        },
+        OpeningBalance {
+            date: day(7, 15),
+            hours: 0.0,
+        },
+        OpeningBalance {
+            date: day(9, 1),
+            hours: -5.0,
+        },
+    ];
+
+    // 10 hours on 01-01 plus 151 days until june
+    let start = 10.0 + difference(day(1, 1), day(6, 1));
+    assert_eq!(
+        cumulate(&periods, start, &balances, &difference),
+        vec![start + 30.0, 17.0, 48.0, 25.0]
+    );
+}
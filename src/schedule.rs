@@ -0,0 +1,239 @@
+use crate::date_helper::parse_weekday;
+use anyhow::anyhow;
+use chrono::{Datelike, NaiveDate};
+use std::collections::BTreeMap;
+
+/// Working hours per weekday
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schedule {
+    /// Indexed by days from monday
+    hours: [f32; 7],
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Schedule::uniform(8.0)
+    }
+}
+
+impl Schedule {
+    /// Same hours from monday to friday
+    pub fn uniform(hours_per_day: f32) -> Schedule {
+        let mut hours = [hours_per_day; 7];
+        hours[5] = 0.0;
+        hours[6] = 0.0;
+        Schedule { hours }
+    }
+
+    /// Schedule from config like `{"Mo": 8, "Di": 8, "Mi": 8, "Do": 8, "Fr": 4}`.
+    /// Days not given are free.
+    pub fn from_names(hours: &BTreeMap<String, f32>) -> Result<Schedule, anyhow::Error> {
+        let mut schedule = Schedule { hours: [0.0; 7] };
+        for (name, hours) in hours {
+            let weekday = parse_weekday(name)
+                .ok_or_else(|| anyhow!("Unknown weekday '{name}' in schedule"))?;
+            if *hours < 0.0 {
+                return Err(anyhow!("Negative hours for '{name}' in schedule"));
+            }
+            schedule.hours[weekday.num_days_from_monday() as usize] = *hours;
+        }
+        Ok(schedule)
+    }
+
+    /// Multiplies all hours, for part-time work
+    pub fn scaled(self, factor: f32) -> Schedule {
+        Schedule {
+            hours: self.hours.map(|hours| hours * factor),
+        }
+    }
+
+    pub fn hours(&self, day: &NaiveDate) -> f32 {
+        self.hours[day.weekday().num_days_from_monday() as usize]
+    }
+}
+
+/// A contract period with its schedule. Without `last_day`, it lasts until the next one starts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contract {
+    pub first_day: NaiveDate,
+    pub last_day: Option<NaiveDate>,
+    pub schedule: Schedule,
+}
+
+/// Working hours of each day, taking contract changes and the time of employment into account
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkingTime {
+    /// Used for days not covered by any contract, if there are no contracts at all
+    default: Schedule,
+    /// Sorted by first day
+    contracts: Vec<Contract>,
+    employment_start: Option<NaiveDate>,
+    employment_end: Option<NaiveDate>,
+}
+
+impl WorkingTime {
+    pub fn new(
+        default: Schedule,
+        mut contracts: Vec<Contract>,
+        employment_start: Option<NaiveDate>,
+        employment_end: Option<NaiveDate>,
+    ) -> Result<WorkingTime, anyhow::Error> {
+        contracts.sort_by_key(|contract| contract.first_day);
+        for contract in &contracts {
+            if matches!(contract.last_day, Some(last_day) if last_day < contract.first_day) {
+                return Err(anyhow!(
+                    "Contract starting {} ends before it starts",
+                    contract.first_day
+                ));
+            }
+        }
+        Ok(WorkingTime {
+            default,
+            contracts,
+            employment_start,
+            employment_end,
+        })
+    }
+
+    fn schedule(&self, day: &NaiveDate) -> Option<&Schedule> {
+        if matches!(self.employment_start, Some(start) if *day < start)
+            || matches!(self.employment_end, Some(end) if *day > end)
+        {
+            return None;
+        }
+        if self.contracts.is_empty() {
+            return Some(&self.default);
+        }
+        self.contracts
+            .iter()
+            .rev()
+            .find(|contract| contract.first_day <= *day)
+            .filter(|contract| !matches!(contract.last_day, Some(last_day) if *day > last_day))
+            .map(|contract| &contract.schedule)
+    }
+
+    pub fn hours(&self, day: &NaiveDate) -> f32 {
+        self.schedule(day)
+            .map(|schedule| schedule.hours(day))
+            .unwrap_or(0.0)
+    }
+
+    pub fn works_on(&self, day: &NaiveDate) -> bool {
+        self.hours(day) > 0.0
+    }
+
+    /// Sums up `weight` of all working days in `from..to`. A weight of `1.0` counts as full day.
+    pub fn count_days(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+        weight: &dyn Fn(&NaiveDate) -> f32,
+    ) -> f32 {
+        self.sum(from, to, &|day| {
+            if self.works_on(day) {
+                weight(day)
+            } else {
+                0.0
+            }
+        })
+    }
+
+    /// Working hours in `from..to`, `weight` is the share of each day which needs to be worked
+    pub fn target_hours(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+        weight: &dyn Fn(&NaiveDate) -> f32,
+    ) -> f32 {
+        self.sum(from, to, &|day| self.hours(day) * weight(day))
+    }
+
+    fn sum(&self, from: NaiveDate, to: NaiveDate, value: &dyn Fn(&NaiveDate) -> f32) -> f32 {
+        let mut cur = from;
+        let mut sum = 0.0;
+        while cur < to {
+            sum += value(&cur);
+            cur = cur.succ();
+        }
+        sum
+    }
+}
+
+#[test]
+fn test_contracts() {
+    let day = |m, d| NaiveDate::from_ymd(2018, m, d);
+    let working_time = WorkingTime::new(
+        Schedule::default(),
+        vec![
+            Contract {
+                first_day: day(7, 1),
+                last_day: None,
+                schedule: Schedule::uniform(8.0).scaled(0.8),
+            },
+            Contract {
+                first_day: day(1, 1),
+                last_day: None,
+                schedule: Schedule::uniform(8.0),
+            },
+        ],
+        Some(day(3, 1)),
+        Some(day(9, 30)),
+    )
+    .unwrap();
+
+    assert_eq!(working_time.hours(&day(2, 28)), 0.0);
+    assert_eq!(working_time.hours(&day(3, 1)), 8.0);
+    assert_eq!(working_time.hours(&day(7, 2)), 6.4);
+    assert_eq!(working_time.hours(&day(10, 1)), 0.0);
+    // june 2018 has 21 working days, july 22
+    let hours = working_time.target_hours(day(6, 1), day(8, 1), &|_| 1.0);
+    assert!((hours - (21.0 * 8.0 + 22.0 * 6.4)).abs() < 0.01);
+}
+
+#[test]
+fn test_schedule() {
+    let config = [
+        ("Mo", 8.0),
+        ("Dienstag", 8.0),
+        ("wed", 8.0),
+        ("Do", 8.0),
+        ("Fr", 4.0),
+    ]
+    .iter()
+    .map(|(name, hours)| (name.to_string(), *hours))
+    .collect();
+    let weekly = Schedule::from_names(&config).unwrap();
+    let schedule = WorkingTime::new(weekly.clone(), vec![], None, None).unwrap();
+
+    // monday to sunday
+    let from = NaiveDate::from_ymd(2018, 10, 15);
+    let to = NaiveDate::from_ymd(2018, 10, 22);
+    assert_eq!(schedule.count_days(from, to, &|_| 1.0), 5.0);
+    assert_eq!(schedule.target_hours(from, to, &|_| 1.0), 36.0);
+    assert_eq!(
+        WorkingTime::new(weekly.scaled(0.5), vec![], None, None)
+            .unwrap()
+            .target_hours(from, to, &|_| 1.0),
+        18.0
+    );
+    assert_eq!(
+        WorkingTime::new(Schedule::default(), vec![], None, None)
+            .unwrap()
+            .target_hours(from, to, &|_| 1.0),
+        40.0
+    );
+
+    let four_days = [("Mo", 10.0), ("Di", 10.0), ("Mi", 10.0), ("Do", 10.0)]
+        .iter()
+        .map(|(name, hours)| (name.to_string(), *hours))
+        .collect();
+    let schedule = WorkingTime::new(
+        Schedule::from_names(&four_days).unwrap(),
+        vec![],
+        None,
+        None,
+    )
+    .unwrap();
+    assert!(!schedule.works_on(&NaiveDate::from_ymd(2018, 10, 19)));
+    assert_eq!(schedule.count_days(from, to, &|_| 1.0), 4.0);
+}
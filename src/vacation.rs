@@ -0,0 +1,161 @@
+use crate::schedule::WorkingTime;
+use anyhow::{anyhow, Context};
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+/// Annual leave as configured in `config.json`, e.g.
+/// `{"days_per_year": 30, "carry_over": {"2018": 4}, "carry_over_expires": "03-31"}`
+#[derive(Deserialize, Debug, Clone)]
+pub struct Entitlement {
+    pub days_per_year: f32,
+    /// Days carried over into the given year. For years without an entry, the rest of
+    /// the previous year is carried over, if that year is part of the queried range.
+    #[serde(default)]
+    pub carry_over: BTreeMap<i32, f32>,
+    /// Last day (`MM-DD`) on which carried over days can be taken
+    pub carry_over_expires: Option<String>,
+}
+
+/// Vacation days of one year
+#[derive(Debug, Clone, PartialEq)]
+pub struct YearBalance {
+    pub year: i32,
+    pub entitlement: f32,
+    pub carry_over: f32,
+    /// Carried over days which weren't taken before they expired
+    pub expired: f32,
+    /// Taken up to today
+    pub taken: f32,
+    /// Planned after today
+    pub planned: f32,
+    pub remaining: f32,
+}
+
+impl Entitlement {
+    fn expiry_in(&self, year: i32) -> Result<Option<NaiveDate>, anyhow::Error> {
+        self.carry_over_expires
+            .as_ref()
+            .map(|date| {
+                NaiveDate::parse_from_str(&format!("{year}-{date}"), "%Y-%m-%d")
+                    .with_context(|| format!("Invalid carry_over_expires '{date}', expected MM-DD"))
+            })
+            .transpose()
+    }
+
+    /// Balance for each of the `years`. `vacation` is the share of a day spent on
+    /// vacation, only working days of `working_time` count.
+    pub fn balance(
+        &self,
+        years: Range<i32>,
+        today: NaiveDate,
+        working_time: &WorkingTime,
+        vacation: &dyn Fn(&NaiveDate) -> f32,
+    ) -> Result<Vec<YearBalance>, anyhow::Error> {
+        let mut result: Vec<YearBalance> = Vec::new();
+
+        for year in years {
+            let first_day = NaiveDate::from_ymd_opt(year, 1, 1)
+                .ok_or_else(|| anyhow!("Invalid year {year}"))?;
+            let next_year = NaiveDate::from_ymd(year + 1, 1, 1);
+            let split = today.succ().max(first_day).min(next_year);
+
+            let carry_over = match (self.carry_over.get(&year), result.last()) {
+                (Some(days), _) => *days,
+                (None, Some(previous)) => previous.remaining.max(0.0),
+                (None, None) => 0.0,
+            };
+            let expired = match self.expiry_in(year)? {
+                Some(expiry) if carry_over > 0.0 => {
+                    let used = working_time.count_days(first_day, expiry.succ(), vacation);
+                    (carry_over - used).max(0.0)
+                }
+                _ => 0.0,
+            };
+            let taken = working_time.count_days(first_day, split, vacation);
+            let planned = working_time.count_days(split, next_year, vacation);
+
+            result.push(YearBalance {
+                year,
+                entitlement: self.days_per_year,
+                carry_over,
+                expired,
+                taken,
+                planned,
+                remaining: self.days_per_year + carry_over - expired - taken - planned,
+            });
+        }
+
+        Ok(result)
+    }
+}
+
+#[test]
+fn test_balance() {
+    let entitlement: Entitlement = serde_json::from_str(
+        r#"{"days_per_year": 30, "carry_over": {"2018": 5}, "carry_over_expires": "03-31"}"#,
+    )
+    .unwrap();
+
+    // two days in march, a week in july up to saturday and a week in january next year
+    let vacation = |d: &NaiveDate| {
+        let day = |y, m, d| NaiveDate::from_ymd(y, m, d);
+        if (day(2018, 3, 1)..=day(2018, 3, 2)).contains(d)
+            || (day(2018, 7, 2)..=day(2018, 7, 7)).contains(d)
+            || (day(2019, 1, 7)..=day(2019, 1, 11)).contains(d)
+        {
+            1.0
+        } else {
+            0.0
+        }
+    };
+
+    let today = NaiveDate::from_ymd(2018, 6, 1);
+    let five_days =
+        WorkingTime::new(crate::schedule::Schedule::default(), vec![], None, None).unwrap();
+    let balance = entitlement
+        .balance(2018..2020, today, &five_days, &vacation)
+        .unwrap();
+    assert_eq!(
+        balance[0],
+        YearBalance {
+            year: 2018,
+            entitlement: 30.0,
+            carry_over: 5.0,
+            expired: 3.0,
+            taken: 2.0,
+            planned: 5.0,
+            remaining: 25.0,
+        }
+    );
+    assert_eq!(balance[1].carry_over, 25.0);
+    assert_eq!(balance[1].planned, 5.0);
+    // nothing taken until the end of march
+    assert_eq!(balance[1].expired, 20.0);
+    assert_eq!(balance[1].remaining, 30.0);
+
+    // the saturday counts if it is a working day
+    let six_days = [
+        ("Mo", 8.0),
+        ("Di", 8.0),
+        ("Mi", 8.0),
+        ("Do", 8.0),
+        ("Fr", 8.0),
+        ("Sa", 4.0),
+    ]
+    .iter()
+    .map(|(name, hours)| (name.to_string(), *hours))
+    .collect();
+    let six_days = WorkingTime::new(
+        crate::schedule::Schedule::from_names(&six_days).unwrap(),
+        vec![],
+        None,
+        None,
+    )
+    .unwrap();
+    let balance = entitlement
+        .balance(2018..2019, today, &six_days, &vacation)
+        .unwrap();
+    assert_eq!(balance[0].planned, 6.0);
+    assert_eq!(balance[0].remaining, 24.0);
+}